@@ -29,12 +29,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let markdown = markdown_exporter.export(&md)?;
     std::fs::write("out.md", markdown.data)?;
 
-    let docx_exporter =
-        DocxExporter::new("Times New Roman".into(), "Arial Black".into(), 22 as usize);
+    let docx_exporter = DocxExporter::builder()
+        .default_font("Times New Roman")
+        .mono_font("Courier New")
+        .font_size(22)
+        .build();
     let docx = docx_exporter.export(&md)?;
     std::fs::write("out.docx", docx.data)?;
 
-    let pdf_exporter = PdfExporter::new(None, &[]);
+    let pdf_exporter = PdfExporter::new(None, None, &[]);
     let pdf = pdf_exporter.export(&md)?;
     std::fs::write("out.pdf", pdf.data)?;
 
@@ -1,64 +1,972 @@
-use std::io::Cursor;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Cursor, Seek, Write},
+    ops::RangeInclusive,
+};
 
+use base64::prelude::{BASE64_STANDARD, Engine as _};
 use bytes::Bytes;
 use derive_new::new;
 use docx_rs::{
-    BreakType, Docx, Paragraph as DocxParagraph, Run as DocxRun, RunFonts, SpecialIndentType,
+    AbstractNumbering, AlignmentType, BorderType, BreakType, Docx, Footer, Footnote, Header,
+    Hyperlink, HyperlinkType, IndentLevel, Level, LevelJc, LevelOverride, LevelText,
+    LineSpacingType, NumPages, NumberFormat, Numbering, NumberingId, PageMargin, PageNum,
+    PageOrientationType, Paragraph as DocxParagraph, ParagraphBorder, ParagraphBorderPosition,
+    ParagraphBorders, ParagraphChild, Pic, Run as DocxRun, RunFonts, Shading, ShdType,
+    SpecialIndentType, Start, Style, StyleType, Table as DocxTable, TableBorder,
+    TableBorderPosition, TableBorders, TableCell as DocxTableCell, TableCellBorder,
+    TableCellBorderPosition, TableCellBorders, TableOfContents, TableRow as DocxTableRow,
+    VertAlignType, WidthType,
 };
-use markdown::{ParseOptions, mdast, mdast::Node};
+use markdown::{Constructs, ParseOptions, mdast, mdast::Node};
 
 use crate::{
     error::MultiFormatExportError,
-    exporter::{Export, Exported},
+    exporter::{Export, Exported, heading_slug},
 };
 
+pub use crate::exporter::ImageResolver;
+
+/// Page margins in twips (1440 twips = 1 inch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageMargins {
+    pub top: i32,
+    pub bottom: i32,
+    pub left: i32,
+    pub right: i32,
+}
+
+impl Default for PageMargins {
+    /// Word's default of 1 inch on every side.
+    fn default() -> Self {
+        Self::uniform(1440)
+    }
+}
+
+impl PageMargins {
+    pub fn uniform(twips: i32) -> Self {
+        Self {
+            top: twips,
+            bottom: twips,
+            left: twips,
+            right: twips,
+        }
+    }
+}
+
+/// Borders and header row styling of rendered tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStyle {
+    /// Width of the single-line borders around every cell, in eighths of a point.
+    pub border_size: usize,
+    /// Hex RGB border colour, e.g. "000000".
+    pub border_color: String,
+    /// Width of the rule under the header row, in eighths of a point.
+    pub header_border_size: usize,
+    /// Hex RGB background of the header cells; `None` leaves them unfilled.
+    pub header_fill: Option<String>,
+    /// Repeat the header row at the top of every page the table spans.
+    pub repeat_header: bool,
+}
+
+impl Default for TableStyle {
+    /// 0.5pt black borders, a 1.5pt rule and light grey fill under a repeating header.
+    fn default() -> Self {
+        Self {
+            border_size: TABLE_BORDER_SIZE,
+            border_color: TABLE_BORDER_COLOR.to_string(),
+            header_border_size: TABLE_HEADER_BORDER_SIZE,
+            header_fill: Some(TABLE_HEADER_FILL.to_string()),
+            repeat_header: true,
+        }
+    }
+}
+
+/// List indentation in twips (1440 twips = 1 inch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListIndentation {
+    /// Left indent of top-level list items.
+    pub base_left: i32,
+    /// Extra left indent per nesting level.
+    pub level_increment: i32,
+    /// Hanging indent reserved for the bullet/number.
+    pub hanging: i32,
+}
+
+impl Default for ListIndentation {
+    /// 0.5" for the first level, 0.25" more per level, 0.25" hanging.
+    fn default() -> Self {
+        Self {
+            base_left: LIST_BASE_LEFT,
+            level_increment: LIST_LEVEL_INCREMENT,
+            hanging: LIST_HANGING,
+        }
+    }
+}
+
+/// Paper size of the generated document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaperSize {
+    #[default]
+    A4,
+    Letter,
+    /// Portrait width and height in twips.
+    Custom {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl PaperSize {
+    // Portrait (width, height) in twips
+    fn dimensions(&self) -> (u32, u32) {
+        match self {
+            PaperSize::A4 => (11906, 16838),
+            PaperSize::Letter => (12240, 15840),
+            PaperSize::Custom { width, height } => (*width, *height),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageOrientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// Word core document properties (File > Info). Unset fields are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+}
+
+impl DocumentMetadata {
+    // docx_rs exposes no setters for these, so `docProps/core.xml` is written here. Same
+    // layout and placeholders as the docx_rs default, plus the configured fields.
+    fn core_properties_xml(&self) -> Vec<u8> {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:dcmitype="http://purl.org/dc/dcmitype/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><dcterms:created xsi:type="dcterms:W3CDTF">1970-01-01T00:00:00Z</dcterms:created>"#,
+        );
+        let author = escape_xml(self.author.as_deref().unwrap_or("unknown"));
+        xml.push_str(&format!(
+            "<dc:creator>{author}</dc:creator><cp:lastModifiedBy>{author}</cp:lastModifiedBy>"
+        ));
+        xml.push_str(r#"<dcterms:modified xsi:type="dcterms:W3CDTF">1970-01-01T00:00:00Z</dcterms:modified><cp:revision>1</cp:revision>"#);
+        for (tag, value) in [
+            ("dc:title", &self.title),
+            ("dc:subject", &self.subject),
+            ("cp:keywords", &self.keywords),
+        ] {
+            if let Some(value) = value {
+                xml.push_str(&format!("<{tag}>{}</{tag}>", escape_xml(value)));
+            }
+        }
+        xml.push_str("</cp:coreProperties>");
+        xml.into_bytes()
+    }
+}
+
+// `value * numerator / denominator` without intermediate overflow
+fn scale_dimension(value: u32, numerator: u32, denominator: u32) -> u32 {
+    (value as u64 * numerator as u64 / denominator.max(1) as u64) as u32
+}
+
+// Word bookmark names are limited to 40 letters, digits and underscores. The leading
+// underscore hides them from Word's bookmark list, as with its own `_Toc` bookmarks.
+fn bookmark_name(slug: &str) -> String {
+    std::iter::once('_')
+        .chain(slug.chars().map(|ch| if ch == '-' { '_' } else { ch }))
+        .take(MAX_BOOKMARK_NAME_LEN)
+        .collect()
+}
+
+// Name of a node's mdast type, for warnings
+fn node_kind(node: &Node) -> &'static str {
+    match node {
+        Node::Root(_) => "Root",
+        Node::Blockquote(_) => "Blockquote",
+        Node::FootnoteDefinition(_) => "FootnoteDefinition",
+        Node::MdxJsxFlowElement(_) => "MdxJsxFlowElement",
+        Node::List(_) => "List",
+        Node::MdxjsEsm(_) => "MdxjsEsm",
+        Node::Toml(_) => "Toml",
+        Node::Yaml(_) => "Yaml",
+        Node::Break(_) => "Break",
+        Node::InlineCode(_) => "InlineCode",
+        Node::InlineMath(_) => "InlineMath",
+        Node::Delete(_) => "Delete",
+        Node::Emphasis(_) => "Emphasis",
+        Node::MdxTextExpression(_) => "MdxTextExpression",
+        Node::FootnoteReference(_) => "FootnoteReference",
+        Node::Html(_) => "Html",
+        Node::Image(_) => "Image",
+        Node::ImageReference(_) => "ImageReference",
+        Node::MdxJsxTextElement(_) => "MdxJsxTextElement",
+        Node::Link(_) => "Link",
+        Node::LinkReference(_) => "LinkReference",
+        Node::Strong(_) => "Strong",
+        Node::Text(_) => "Text",
+        Node::Code(_) => "Code",
+        Node::Math(_) => "Math",
+        Node::MdxFlowExpression(_) => "MdxFlowExpression",
+        Node::Heading(_) => "Heading",
+        Node::Table(_) => "Table",
+        Node::ThematicBreak(_) => "ThematicBreak",
+        Node::TableRow(_) => "TableRow",
+        Node::TableCell(_) => "TableCell",
+        Node::ListItem(_) => "ListItem",
+        Node::Definition(_) => "Definition",
+        Node::Paragraph(_) => "Paragraph",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Horizontal alignment of heading paragraphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadingAlignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl HeadingAlignment {
+    fn alignment_type(self) -> AlignmentType {
+        match self {
+            HeadingAlignment::Left => AlignmentType::Left,
+            HeadingAlignment::Center => AlignmentType::Center,
+            HeadingAlignment::Right => AlignmentType::Right,
+            HeadingAlignment::Justify => AlignmentType::Both,
+        }
+    }
+}
+
 #[derive(new)]
 pub struct DocxExporter {
     default_font_family: String, // e.g. "Times New Roman"
     mono_font_family: String,    // e.g. "Courier New"
-    default_font_size: usize,    // half-points (22 = 11pt)
+    default_font_size: usize,    // half-points (22 = 11pt); 0 = DEFAULT_FONT_SIZE
+    #[new(default)]
+    image_resolver: Option<ImageResolver>,
+    #[new(value = "DEFAULT_MAX_IMAGE_WIDTH")]
+    max_image_width: u32, // EMUs (914400 = 1 inch)
+    #[new(default)]
+    page_margins: Option<PageMargins>, // None = 1" on every side
+    #[new(default)]
+    paper_size: PaperSize,
+    #[new(default)]
+    orientation: PageOrientation,
+    #[new(value = "DEFAULT_CODE_BLOCK_FILL.to_string()")]
+    code_block_fill: String, // hex RGB, e.g. "F5F5F5"
+    #[new(default)]
+    toc: bool,
+    #[new(value = "DEFAULT_TOC_LEVELS")]
+    toc_levels: RangeInclusive<usize>, // heading depths listed in the TOC
+    #[new(default)]
+    east_asian_font_family: Option<String>, // e.g. "SimSun"; None = left to Word
+    #[new(default)]
+    heading_alignments: [Option<HeadingAlignment>; 6], // indexed by depth - 1; None = left
+    #[new(default)]
+    line_spacing_multiplier: Option<f32>, // body/list line height, e.g. 2.0; None = single
+    #[new(default)]
+    bullet_chars: Vec<String>, // bullet glyph per depth (cycled); empty = "•"
+    #[new(value = "Some(DEFAULT_LINK_COLOR.to_string())")]
+    link_color: Option<String>, // hex RGB; None = same colour as the surrounding text
+    #[new(value = "true")]
+    link_underline: bool,
+    #[new(default)]
+    page_break_before_h1: bool,
+    #[new(default)]
+    header_text: Option<String>,
+    #[new(default)]
+    footer_page_numbers: bool, // "Page X of Y"
+    #[new(default)]
+    caption_images: bool,
+    #[new(value = "true")]
+    number_figures: bool, // "Figure N: " caption prefix
+    #[new(default)]
+    autolink_urls: bool,
+    #[new(value = "Constructs::gfm()")]
+    constructs: Constructs, // markdown syntax recognised by the parser
+    #[new(value = "true")]
+    detect_bold_line_headings: bool,
+    #[new(default)]
+    code_line_numbers: bool,
+    #[new(default)]
+    show_code_language: bool,
+    #[new(default)]
+    metadata: Option<DocumentMetadata>,
+    #[new(value = "DEFAULT_HIGHLIGHT_COLOR.to_string()")]
+    highlight_color: String, // Word highlight colour name, e.g. "yellow"
+    #[new(default)]
+    rtl: bool,
+    #[new(default)]
+    list_indentation: ListIndentation,
+    #[new(default)]
+    table_style: TableStyle,
+    #[new(value = "DEFAULT_HEADING_SIZE_MULTIPLIERS")]
+    heading_size_multipliers: [f32; 6], // heading size relative to body, per depth
+    #[new(value = "true")]
+    keep_headings_with_next: bool,
 }
 
-const DOCX_MIME: &'static str =
-    "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
-const DOCX_EXTENSION: &'static str = "docx";
+/// Named alternative to the positional [`DocxExporter::new`]:
+///
+/// ```
+/// use multi_format_export_rs::exporter::docx::DocxExporter;
+///
+/// let exporter = DocxExporter::builder()
+///     .default_font("Times New Roman")
+///     .mono_font("Courier New")
+///     .font_size(22)
+///     .build();
+/// ```
+///
+/// Layout options are set on the built exporter through its `with_*` methods.
+#[derive(Debug, Clone)]
+pub struct DocxExporterBuilder {
+    default_font_family: String,
+    mono_font_family: String,
+    default_font_size: usize,
+}
+
+impl Default for DocxExporterBuilder {
+    fn default() -> Self {
+        Self {
+            default_font_family: "Times New Roman".to_string(),
+            mono_font_family: "Courier New".to_string(),
+            default_font_size: DEFAULT_FONT_SIZE,
+        }
+    }
+}
+
+impl DocxExporterBuilder {
+    /// Body text font family (default `Times New Roman`).
+    pub fn default_font(mut self, family: impl Into<String>) -> Self {
+        self.default_font_family = family.into();
+        self
+    }
+
+    /// Font family for inline code and code blocks (default `Courier New`).
+    pub fn mono_font(mut self, family: impl Into<String>) -> Self {
+        self.mono_font_family = family.into();
+        self
+    }
+
+    /// Body font size in half-points (default 22 = 11pt). Zero falls back to the default.
+    pub fn font_size(mut self, half_points: usize) -> Self {
+        self.default_font_size = half_points;
+        self
+    }
+
+    pub fn build(self) -> DocxExporter {
+        DocxExporter::new(
+            self.default_font_family,
+            self.mono_font_family,
+            self.default_font_size,
+        )
+    }
+}
+
+const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+const DOCX_EXTENSION: &str = "docx";
+const DEFAULT_FONT_SIZE: usize = 22; // half-points (11pt)
 
 // Indentation (in twips: 1440 twips = 1 inch)
 const LIST_BASE_LEFT: i32 = 720; // 0.5"
 const LIST_LEVEL_INCREMENT: i32 = 360; // 0.25"
 const LIST_HANGING: i32 = 360; // Hanging indent for bullet/number
 
-impl Default for DocxExporter {
-    fn default() -> Self {
+// Numbering ids (abstract/numbering id 1 is reserved for the docx_rs default definition)
+const BULLET_ABSTRACT_ID: usize = 2;
+const ORDERED_ABSTRACT_ID: usize = 3;
+const FIRST_NUMBERING_ID: usize = 2;
+const MAX_LIST_LEVEL: usize = 8;
+const DEFAULT_BULLET: &str = "•";
+
+// Blockquotes
+const QUOTE_INDENT: i32 = 720; // 0.5" per nesting level
+const QUOTE_BORDER_SIZE: usize = 18; // eighths of a point (2.25pt)
+const QUOTE_BORDER_SPACE: usize = 8; // points between border and text
+const QUOTE_BORDER_COLOR: &str = "BFBFBF";
+const QUOTE_TEXT_COLOR: &str = "595959";
+
+// Line spacing (auto rule: 240 = single)
+const SINGLE_LINE_SPACING: f32 = 240.0;
+
+// Definition lists
+const DEFINITION_INDENT: i32 = 720; // 0.5"
+
+// Thematic breaks
+const RULE_BORDER_SIZE: usize = 6; // eighths of a point (0.75pt)
+const RULE_SPACING: u32 = 120; // twips before/after the rule
+
+// Code blocks
+const DEFAULT_CODE_BLOCK_FILL: &str = "F5F5F5";
+const CODE_BORDER_SIZE: usize = 4; // eighths of a point (0.5pt)
+const CODE_BORDER_COLOR: &str = "D9D9D9";
+const CODE_LINE_NUMBER_COLOR: &str = "A6A6A6";
+const CODE_LABEL_SPACING: u32 = 40; // twips between the language label and the block
+const FULL_WIDTH_PCT: usize = 5000; // fiftieths of a percent
+
+// Tables
+const TABLE_BORDER_SIZE: usize = 4; // eighths of a point (0.5pt)
+const TABLE_BORDER_COLOR: &str = "000000";
+const TABLE_HEADER_BORDER_SIZE: usize = 12; // eighths of a point (1.5pt)
+const TABLE_HEADER_FILL: &str = "F2F2F2";
+// docx_rs has no `w:tblHeader`; header rows carry `w:cantSplit` (nothing else sets it)
+// and the flag is spliced in next to it after serialisation
+const TABLE_ROW_CANT_SPLIT: &str = "<w:cantSplit />";
+const TABLE_HEADER_ROW: &str = "<w:cantSplit /><w:tblHeader />";
+
+// Headings
+const DEFAULT_HEADING_SIZE_MULTIPLIERS: [f32; 6] = [1.60, 1.45, 1.30, 1.15, 1.05, 1.00];
+const MAX_BOOKMARK_NAME_LEN: usize = 40;
+
+// Table of contents
+const DEFAULT_TOC_LEVELS: RangeInclusive<usize> = 1..=3;
+
+// `==marked==` text
+const HIGHLIGHT_DELIMITER: &str = "==";
+const DEFAULT_HIGHLIGHT_COLOR: &str = "yellow";
+
+// Word's built-in "Hyperlink" character style colour
+const DEFAULT_LINK_COLOR: &str = "0563C1";
+
+// Character style applied by docx_rs to footnote reference runs
+const FOOTNOTE_REFERENCE_STYLE: &str = "FootnoteReference";
+
+// Images (in EMUs: 914400 EMUs = 1 inch, 9525 EMUs = 1px at 96 dpi)
+const EMU_PER_PIXEL: u64 = 9525;
+const DEFAULT_MAX_IMAGE_WIDTH: u32 = 5_486_400; // 6"
+
+// Run formatting inherited by nested inline nodes (e.g. bold inside strikethrough)
+#[derive(Clone, Copy, Default)]
+struct InlineStyle {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    underline: bool,
+    highlight: bool,
+    mono: bool,
+    size: usize,                       // half-points; 0 = body size
+    vert_align: Option<VertAlignType>, // sub/superscript
+}
+
+impl InlineStyle {
+    fn heading(size: usize) -> Self {
         Self {
-            default_font_family: "Times New Roman".to_string(),
-            mono_font_family: "Courier New".to_string(),
-            default_font_size: 22, // 11pt
+            bold: true,
+            size,
+            ..Self::default()
+        }
+    }
+}
+
+// Formatting opened by raw HTML tags (`<b>`, `<i>`, ...). Counters rather than flags so
+// nested or repeated tags close correctly; stray closing tags are ignored.
+#[derive(Default)]
+struct HtmlSpans {
+    bold: usize,
+    italic: usize,
+    underline: usize,
+    strike: usize,
+    mono: usize,
+}
+
+impl HtmlSpans {
+    // Tags without a formatting mapping are ignored (stripped)
+    fn toggle(&mut self, tag: &str, closing: bool) {
+        let counter = match tag {
+            "b" | "strong" => &mut self.bold,
+            "i" | "em" => &mut self.italic,
+            "u" | "ins" => &mut self.underline,
+            "s" | "strike" | "del" => &mut self.strike,
+            "code" | "kbd" | "tt" => &mut self.mono,
+            _ => return,
+        };
+        *counter = if closing {
+            counter.saturating_sub(1)
+        } else {
+            *counter + 1
+        };
+    }
+
+    fn apply(&self, style: InlineStyle) -> InlineStyle {
+        InlineStyle {
+            bold: style.bold || self.bold > 0,
+            italic: style.italic || self.italic > 0,
+            underline: style.underline || self.underline > 0,
+            strike: style.strike || self.strike > 0,
+            mono: style.mono || self.mono > 0,
+            ..style
+        }
+    }
+}
+
+// One line of a compact definition list paragraph (`Term` / `: definition`)
+enum DefinitionLine {
+    Term(Vec<Node>),
+    Definition(Vec<Node>),
+}
+
+// Pieces of a raw HTML fragment
+enum HtmlToken<'a> {
+    Text(&'a str),
+    Tag { name: String, closing: bool },
+}
+
+// Word bodies are a sequence of paragraphs and tables
+enum DocxBlock {
+    Paragraph(Box<DocxParagraph>),
+    Table(Box<DocxTable>),
+}
+
+impl From<DocxParagraph> for DocxBlock {
+    fn from(para: DocxParagraph) -> Self {
+        DocxBlock::Paragraph(Box::new(para))
+    }
+}
+
+impl From<DocxTable> for DocxBlock {
+    fn from(table: DocxTable) -> Self {
+        DocxBlock::Table(Box::new(table))
+    }
+}
+
+// Per-export mutable state. The exporter itself stays immutable (and shareable between
+// threads); anything collected while walking the AST lives here instead.
+#[derive(Default)]
+struct RenderState {
+    numberings: Vec<Numbering>,
+    footnote_definitions: HashMap<String, Vec<Node>>, // identifier -> definition body
+    link_definitions: HashMap<String, mdast::Definition>, // `[label]: url` by identifier
+    // docx_rs only collects footnotes referenced from top-level paragraphs, so references
+    // inside table cells, hyperlinks and other notes fall back to a plain superscript label
+    footnote_fallback: bool,
+    seen_heading: bool,
+    in_link: bool, // link text must not be autolinked again
+    figure_count: usize,
+    // Captions of images rendered inline; the enclosing block emits them right after the
+    // paragraph that holds the picture
+    pending_captions: Vec<DocxParagraph>,
+    warnings: Vec<String>,          // one entry per kind of dropped node
+    heading_slugs: HashSet<String>, // anchors handed out so far, for `-1`, `-2` suffixes
+    heading_bookmarks: HashMap<String, String>, // heading anchor -> its bookmark name
+    bookmark_names: HashSet<String>,
+}
+
+impl RenderState {
+    // Repeated headings get `-1`, `-2`, ... appended, like HTML heading anchors
+    fn unique_heading_slug(&mut self, text: &str) -> String {
+        let base = heading_slug(text);
+        let mut slug = base.clone();
+        let mut suffix = 0;
+        while !self.heading_slugs.insert(slug.clone()) {
+            suffix += 1;
+            slug = format!("{base}-{suffix}");
+        }
+        slug
+    }
+
+    // Links may point at headings further down, so every heading's bookmark is named
+    // up front. Rendering hands out the same anchors again, in the same order.
+    fn collect_heading_bookmarks(&mut self, node: &Node) {
+        if let Node::Heading(_) = node {
+            let slug = self.unique_heading_slug(&node.to_string());
+            let name = self.unique_bookmark_name(&slug);
+            self.heading_bookmarks.insert(slug, name);
+        }
+        if let Some(children) = node.children() {
+            for child in children {
+                self.collect_heading_bookmarks(child);
+            }
+        }
+    }
+
+    // Anchors are unique, but cutting them to Word's length limit can make two equal
+    // again; a taken name is shortened further to make room for a `_1`, `_2` suffix
+    fn unique_bookmark_name(&mut self, slug: &str) -> String {
+        let base = bookmark_name(slug);
+        let mut name = base.clone();
+        let mut suffix = 0;
+        while !self.bookmark_names.insert(name.clone()) {
+            suffix += 1;
+            let suffix = format!("_{suffix}");
+            name = base
+                .chars()
+                .take(MAX_BOOKMARK_NAME_LEN - suffix.len())
+                .chain(suffix.chars())
+                .collect();
+        }
+        name
+    }
+
+    // Text shown for an image that could not be loaded: its alt text, or its URL when there
+    // is none, so the gap stays visible. Also reported as a warning.
+    fn image_fallback(&mut self, image: &mdast::Image) -> String {
+        // data URIs can be megabytes long
+        let (source, url) = if image.url.starts_with("data:") {
+            ("with embedded data", "[image]")
+        } else {
+            (image.url.as_str(), image.url.as_str())
+        };
+        let warning = format!("image {source} left out: could not be loaded");
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+        let alt = DocxExporter::split_size_hint(&image.alt).0.trim();
+        if alt.is_empty() { url } else { alt }.to_string()
+    }
+
+    fn warn_dropped(&mut self, node: &Node) {
+        let warning = format!("unsupported markdown node dropped: {}", node_kind(node));
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
         }
     }
+
+    // Footnote and link definitions may appear anywhere in the document (even after their
+    // first reference), so they are gathered up front. The first definition of a label wins.
+    fn collect_definitions(&mut self, node: &Node) {
+        match node {
+            Node::FootnoteDefinition(definition) => {
+                self.footnote_definitions
+                    .entry(definition.identifier.clone())
+                    .or_insert_with(|| definition.children.clone());
+            }
+            Node::Definition(definition) => {
+                self.link_definitions
+                    .entry(definition.identifier.clone())
+                    .or_insert_with(|| definition.clone());
+            }
+            _ => {}
+        }
+        if let Some(children) = node.children() {
+            for child in children {
+                self.collect_definitions(child);
+            }
+        }
+    }
+
+    // Each rendered list gets its own numbering instance so numbering restarts per list
+    // and honours the list's start value.
+    fn add_list_numbering(&mut self, ordered: bool, level: usize, start: usize) -> usize {
+        let id = FIRST_NUMBERING_ID + self.numberings.len();
+        let abstract_id = if ordered {
+            ORDERED_ABSTRACT_ID
+        } else {
+            BULLET_ABSTRACT_ID
+        };
+        self.numberings.push(
+            Numbering::new(id, abstract_id).add_override(LevelOverride::new(level).start(start)),
+        );
+        id
+    }
+}
+
+impl Default for DocxExporter {
+    fn default() -> Self {
+        DocxExporterBuilder::default().build()
+    }
 }
 
 impl DocxExporter {
+    // ---------------- Options ----------------
+
+    pub fn builder() -> DocxExporterBuilder {
+        DocxExporterBuilder::default()
+    }
+
+    /// Set the resolver used to load images referenced as `![alt](url)`.
+    /// Without a resolver every image is rendered as its italic alt text, except inline
+    /// base64 `data:` URIs which are decoded directly.
+    pub fn with_image_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.image_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Maximum display width of embedded images in EMUs; wider images are scaled down.
+    pub fn with_max_image_width(mut self, max_image_width: u32) -> Self {
+        self.max_image_width = max_image_width;
+        self
+    }
+
+    /// Set the page margins (in twips). Defaults to Word's 1 inch on every side.
+    pub fn with_page_margins(mut self, page_margins: PageMargins) -> Self {
+        self.page_margins = Some(page_margins);
+        self
+    }
+
+    /// Set the paper size. Defaults to A4.
+    pub fn with_paper_size(mut self, paper_size: PaperSize) -> Self {
+        self.paper_size = paper_size;
+        self
+    }
+
+    /// Set the page orientation; width and height of the paper size are swapped for landscape.
+    pub fn with_orientation(mut self, orientation: PageOrientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Background fill of fenced code blocks as hex RGB (default `F5F5F5`).
+    pub fn with_code_block_fill(mut self, fill: impl Into<String>) -> Self {
+        self.code_block_fill = fill.into();
+        self
+    }
+
+    /// Insert a Table of Contents field before the body. Word builds/refreshes it from the
+    /// heading styles when the document is opened.
+    pub fn with_toc(mut self, toc: bool) -> Self {
+        self.toc = toc;
+        self
+    }
+
+    /// Heading depths included in the Table of Contents (default `1..=3`).
+    pub fn with_toc_levels(mut self, toc_levels: RangeInclusive<usize>) -> Self {
+        self.toc_levels = toc_levels;
+        self
+    }
+
+    /// Font used for East Asian (CJK) characters, e.g. `SimSun`. Latin text keeps the
+    /// body/mono font; without this Word picks the CJK fallback itself.
+    pub fn with_east_asian_font_family(mut self, family: impl Into<String>) -> Self {
+        self.east_asian_font_family = Some(family.into());
+        self
+    }
+
+    /// Align headings of the given depths (e.g. `1..=2` for centred titles). Can be called
+    /// repeatedly for different depths; unset depths stay left-aligned.
+    pub fn with_heading_alignment(
+        mut self,
+        depths: RangeInclusive<usize>,
+        alignment: HeadingAlignment,
+    ) -> Self {
+        for depth in depths.filter(|depth| (1..=6).contains(depth)) {
+            self.heading_alignments[depth - 1] = Some(alignment);
+        }
+        self
+    }
+
+    /// Line height of body paragraphs and list items as a multiple of single spacing
+    /// (e.g. `1.5` or `2.0`). Heading spacing is unaffected.
+    pub fn with_line_spacing_multiplier(mut self, multiplier: f32) -> Self {
+        self.line_spacing_multiplier = Some(multiplier);
+        self
+    }
+
+    /// Bullet glyphs for unordered lists, cycled by nesting depth (e.g. `•`, `◦`, `▪`).
+    /// An empty list uses `•` at every level.
+    pub fn with_bullet_chars<I, S>(mut self, bullet_chars: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.bullet_chars = bullet_chars.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Colour of hyperlink text as hex RGB (default Word blue `0563C1`); `None` keeps the
+    /// colour of the surrounding text.
+    pub fn with_link_color<C: Into<Option<String>>>(mut self, color: C) -> Self {
+        self.link_color = color.into();
+        self
+    }
+
+    /// Underline hyperlink text (default `true`).
+    pub fn with_link_underline(mut self, underline: bool) -> Self {
+        self.link_underline = underline;
+        self
+    }
+
+    /// Start every H1 on a new page (chapters). The first heading of the document never
+    /// gets a break, so there is no leading blank page.
+    pub fn with_page_break_before_h1(mut self, page_break_before_h1: bool) -> Self {
+        self.page_break_before_h1 = page_break_before_h1;
+        self
+    }
+
+    /// Text shown centred in the page header of every page.
+    pub fn with_header_text(mut self, header_text: impl Into<String>) -> Self {
+        self.header_text = Some(header_text.into());
+        self
+    }
+
+    /// Show a centred "Page X of Y" footer on every page.
+    pub fn with_footer_page_numbers(mut self, footer_page_numbers: bool) -> Self {
+        self.footer_page_numbers = footer_page_numbers;
+        self
+    }
+
+    /// Render the alt text of embedded images as an italic, centred caption paragraph
+    /// below the picture. Images with empty alt text get no caption.
+    pub fn with_image_captions(mut self, caption_images: bool) -> Self {
+        self.caption_images = caption_images;
+        self
+    }
+
+    /// Prefix image captions with an auto-incremented "Figure N: " (default `true`).
+    pub fn with_figure_numbers(mut self, number_figures: bool) -> Self {
+        self.number_figures = number_figures;
+        self
+    }
+
+    /// Turn bare `http(s)://` and `www.` URLs in plain text into hyperlinks. Only needed
+    /// when GFM autolink literals are not parsed; links already in the markdown are
+    /// unaffected. Off by default.
+    pub fn with_autolink_urls(mut self, autolink_urls: bool) -> Self {
+        self.autolink_urls = autolink_urls;
+        self
+    }
+
+    /// Parse GitHub Flavored Markdown (tables, strikethrough, task lists, autolink
+    /// literals, footnotes). On by default; `false` parses strict CommonMark.
+    pub fn with_gfm(mut self, gfm: bool) -> Self {
+        self.constructs = if gfm {
+            Constructs::gfm()
+        } else {
+            Constructs::default()
+        };
+        self
+    }
+
+    /// Fine-grained control over the markdown constructs the parser recognises.
+    pub fn with_constructs(mut self, constructs: Constructs) -> Self {
+        self.constructs = constructs;
+        self
+    }
+
+    /// Treat a paragraph that starts with a bold line as a level-2 heading (default `true`).
+    /// It triggers for exactly two shapes:
+    ///
+    /// - a paragraph consisting only of one `**bold**` span, e.g. `**Summary**`;
+    /// - a `**bold**` span, a soft line break, then plain unformatted text, e.g.
+    ///   `**Summary**` on one line and the body text on the next. The bold line becomes the
+    ///   heading and the remaining lines a normal paragraph.
+    ///
+    /// Bold text followed by more text on the same line, a hard break, or formatted text
+    /// on the following lines never triggers it. With `false`
+    /// such paragraphs are rendered as ordinary bold text.
+    pub fn with_detect_bold_line_headings(mut self, detect: bool) -> Self {
+        self.detect_bold_line_headings = detect;
+        self
+    }
+
+    /// Prefix every line of fenced code blocks with a grey, right-aligned line number.
+    pub fn with_code_line_numbers(mut self, code_line_numbers: bool) -> Self {
+        self.code_line_numbers = code_line_numbers;
+        self
+    }
+
+    /// Show the fence language (e.g. `RUST`) as a small bold label above code blocks.
+    pub fn with_show_code_language(mut self, show_code_language: bool) -> Self {
+        self.show_code_language = show_code_language;
+        self
+    }
+
+    /// Title, author, subject and keywords stored in the document's core properties.
+    pub fn with_metadata(mut self, metadata: DocumentMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Highlight colour of `==marked==` text (default `yellow`). Word only accepts its named
+    /// highlight colours: `yellow`, `green`, `cyan`, `magenta`, `blue`, `red`, `darkBlue`,
+    /// `darkCyan`, `darkGreen`, `darkMagenta`, `darkRed`, `darkYellow`, `darkGray`,
+    /// `lightGray` and `black`.
+    pub fn with_highlight_color(mut self, color: impl Into<String>) -> Self {
+        self.highlight_color = color.into();
+        self
+    }
+
+    /// Right-to-left documents (Arabic, Hebrew): body, heading, list and table paragraphs
+    /// run from the right margin, with list markers and indentation mirrored. docx_rs has
+    /// no run-level RTL flag, so character order within runs is left to Word's bidi
+    /// algorithm.
+    pub fn with_rtl(mut self, rtl: bool) -> Self {
+        self.rtl = rtl;
+        self
+    }
+
+    /// Indentation of list items; defaults to 0.5" + 0.25" per level with a 0.25" hanging
+    /// indent.
+    pub fn with_list_indentation(mut self, list_indentation: ListIndentation) -> Self {
+        self.list_indentation = list_indentation;
+        self
+    }
+
+    /// Borders, header fill and header repetition of tables. Defaults to 0.5pt borders
+    /// with a bold, light grey header row that repeats across page breaks.
+    pub fn with_table_style(mut self, table_style: TableStyle) -> Self {
+        self.table_style = table_style;
+        self
+    }
+
+    /// Heading font size as a multiple of the body size, for depths 1 through 6. Defaults
+    /// to `[1.60, 1.45, 1.30, 1.15, 1.05, 1.00]`; results are clamped to 1pt..200pt.
+    pub fn with_heading_size_multipliers(mut self, multipliers: [f32; 6]) -> Self {
+        self.heading_size_multipliers = multipliers;
+        self
+    }
+
+    /// Keep each heading on the same page as the paragraph that follows it, so headings
+    /// are never stranded at the bottom of a page. On by default.
+    pub fn with_keep_headings_with_next(mut self, keep: bool) -> Self {
+        self.keep_headings_with_next = keep;
+        self
+    }
+
+    // Page (width, height) in twips after applying the orientation
+    fn page_dimensions(&self) -> (u32, u32) {
+        let (short, long) = {
+            let (w, h) = self.paper_size.dimensions();
+            (w.min(h), w.max(h))
+        };
+        match self.orientation {
+            PageOrientation::Portrait => (short, long),
+            PageOrientation::Landscape => (long, short),
+        }
+    }
+
+    // Body size in half-points; an unset (zero) size falls back to 11pt so headings and
+    // spacing, which scale from it, never collapse
+    fn body_font_size(&self) -> usize {
+        match self.default_font_size {
+            0 => DEFAULT_FONT_SIZE,
+            size => size,
+        }
+    }
+
     // ---------------- Headings ----------------
 
     // Map heading depth (1..=6) to half-point font sizes (Word uses half-points: 32 = 16pt)
     fn heading_font_size(&self, depth: usize) -> usize {
-        let body_half = self.default_font_size as f32;
-        let multiplier = match depth {
-            1 => 1.60, // roughly 176% of body
-            2 => 1.45,
-            3 => 1.30,
-            4 => 1.15,
-            5 => 1.05,
-            _ => 1.00,
-        };
-        let mut hp = (body_half * multiplier).round() as isize;
-        if hp < 2 {
-            hp = 2;
-        } // avoid zero / invalid
-        if hp > 400 {
-            hp = 400;
-        } // ~200pt
+        let body_half = self.body_font_size() as f32;
+        let multiplier = self.heading_size_multipliers[depth.clamp(1, 6) - 1];
+        // Clamp to avoid zero / invalid sizes and to cap at ~200pt
+        let hp = ((body_half * multiplier).round() as isize).clamp(2, 400);
         hp as usize
     }
 
@@ -71,7 +979,7 @@ impl DocxExporter {
             3 => (300, 140),
             _ => (240, 120),
         };
-        let body_pt = self.default_font_size as f32 / 2.0;
+        let body_pt = self.body_font_size() as f32 / 2.0;
         let ratio = body_pt / 11.0; // 11pt was the original implicit baseline
         let scale = |v: u32| -> u32 {
             let scaled = (v as f32 * ratio).round();
@@ -81,17 +989,72 @@ impl DocxExporter {
         (scale(base_before), scale(base_after))
     }
 
-    fn render_heading_node(&self, heading: &mdast::Heading) -> DocxParagraph {
+    fn heading_style_id(depth: usize) -> String {
+        format!("Heading{}", depth.clamp(1, 6))
+    }
+
+    // Word's built-in heading styles ("heading 1".."heading 6" with outline levels) so the
+    // navigation pane and TOC fields see the headings. Paragraphs still set their own size and
+    // spacing, which override the style.
+    fn heading_styles(&self) -> Vec<Style> {
+        (1..=6)
+            .map(|depth| {
+                Style::new(Self::heading_style_id(depth), StyleType::Paragraph)
+                    .name(format!("heading {depth}"))
+                    .based_on("Normal")
+                    .next("Normal")
+                    .outline_lvl(depth - 1)
+                    .bold()
+                    .size(self.heading_font_size(depth))
+            })
+            .collect()
+    }
+
+    fn new_heading_paragraph(&self, depth: usize) -> DocxParagraph {
+        let (before, after) = self.heading_spacing(depth);
+        let para = self.apply_text_direction(
+            DocxParagraph::new()
+                .style(&Self::heading_style_id(depth))
+                .line_spacing(docx_rs::LineSpacing::new().before(before).after(after))
+                .keep_next(self.keep_headings_with_next),
+        );
+        match self.heading_alignments[depth.clamp(1, 6) - 1] {
+            Some(alignment) => para.align(alignment.alignment_type()),
+            None => para,
+        }
+    }
+
+    fn render_heading_node(
+        &self,
+        heading: &mdast::Heading,
+        state: &mut RenderState,
+    ) -> DocxParagraph {
         let depth = heading.depth as usize;
         let size = self.heading_font_size(depth);
-        let (before, after) = self.heading_spacing(depth);
+        let mut p = self.new_heading_paragraph(depth);
+        if self.page_break_before_h1 && depth == 1 && state.seen_heading {
+            p = p.page_break_before(true);
+        }
+        state.seen_heading = true;
 
-        let mut p = DocxParagraph::new()
-            .line_spacing(docx_rs::LineSpacing::new().before(before).after(after));
+        // Bookmark the heading so `[text](#slug)` links can jump to it
+        let plain_text: String = heading.children.iter().map(Node::to_string).collect();
+        let slug = state.unique_heading_slug(&plain_text);
+        let bookmark_id = state.heading_slugs.len();
+        let name = match state.heading_bookmarks.get(&slug) {
+            Some(name) => name.clone(),
+            None => state.unique_bookmark_name(&slug),
+        };
+        p = p.add_bookmark_start(bookmark_id, name);
 
         // Inline children -> all runs with base heading size
-        p = self.append_inline_children_with_base(p, &heading.children, true, false, size, false);
-        p
+        p = self.append_inline_children_with_base(
+            p,
+            &heading.children,
+            InlineStyle::heading(size),
+            state,
+        );
+        p.add_bookmark_end(bookmark_id)
     }
 
     // Pattern: bold first line treated as heading2
@@ -102,12 +1065,11 @@ impl DocxExporter {
         if p.children.len() == 1 {
             return matches!(p.children[0], Node::Strong(_));
         }
-        if p.children.len() == 2 {
-            if let Node::Strong(_) = p.children[0] {
-                if let Node::Text(t) = &p.children[1] {
-                    return t.value.starts_with('\n');
-                }
-            }
+        if p.children.len() == 2
+            && let Node::Strong(_) = p.children[0]
+            && let Node::Text(t) = &p.children[1]
+        {
+            return t.value.starts_with('\n');
         }
         false
     }
@@ -115,23 +1077,20 @@ impl DocxExporter {
     fn split_paragraph_heading(
         &self,
         p: &mdast::Paragraph,
+        state: &mut RenderState,
     ) -> Option<(DocxParagraph, Option<DocxParagraph>)> {
-        if !self.is_strong_line_heading(p) {
+        if !self.detect_bold_line_headings || !self.is_strong_line_heading(p) {
             return None;
         }
         // Treat as level 2 heading
         let size = self.heading_font_size(2);
-        let (before, after) = self.heading_spacing(2);
-        let mut heading_para = DocxParagraph::new()
-            .line_spacing(docx_rs::LineSpacing::new().before(before).after(after));
+        let mut heading_para = self.new_heading_paragraph(2);
 
         heading_para = self.append_inline_children_with_base(
             heading_para,
             &p.children[0..1],
-            true,
-            false,
-            size,
-            false,
+            InlineStyle::heading(size),
+            state,
         );
 
         if p.children.len() == 1 {
@@ -151,10 +1110,8 @@ impl DocxExporter {
             body_para = self.append_inline_children_with_base(
                 body_para,
                 std::slice::from_ref(&remainder_node),
-                false,
-                false,
-                0,
-                false,
+                InlineStyle::default(),
+                state,
             );
             Some((heading_para, Some(body_para)))
         } else {
@@ -164,13 +1121,116 @@ impl DocxExporter {
 
     // --------------- Lists with real indentation (not spaces) ---------------
 
-    fn list_left_indent(depth: usize) -> i32 {
-        LIST_BASE_LEFT + (depth as i32) * LIST_LEVEL_INCREMENT
+    fn list_left_indent(&self, depth: usize) -> i32 {
+        let indentation = &self.list_indentation;
+        indentation.base_left + (depth as i32) * indentation.level_increment
+    }
+
+    // Clamp to the nine levels Word supports per numbering definition
+    fn list_level(depth: usize) -> usize {
+        depth.min(MAX_LIST_LEVEL)
+    }
+
+    // Ordered lists cycle 1. / a. / i. with depth, like Word's default multilevel list
+    fn ordered_level_format(level: usize) -> &'static str {
+        match level % 3 {
+            0 => "decimal",
+            1 => "lowerLetter",
+            _ => "lowerRoman",
+        }
+    }
+
+    fn bullet_char(&self, level: usize) -> &str {
+        if self.bullet_chars.is_empty() {
+            return DEFAULT_BULLET;
+        }
+        &self.bullet_chars[level % self.bullet_chars.len()]
+    }
+
+    // One abstract definition per list kind; every level carries its own indentation so
+    // Word can re-indent/restyle the list natively.
+    fn list_abstract_numbering(&self, ordered: bool) -> AbstractNumbering {
+        let abstract_id = if ordered {
+            ORDERED_ABSTRACT_ID
+        } else {
+            BULLET_ABSTRACT_ID
+        };
+        let mut numbering = AbstractNumbering::new(abstract_id);
+        for level in 0..=MAX_LIST_LEVEL {
+            let (format, text) = if ordered {
+                (
+                    Self::ordered_level_format(level),
+                    format!("%{}.", level + 1),
+                )
+            } else {
+                ("bullet", self.bullet_char(level).to_string())
+            };
+            numbering = numbering.add_level(
+                Level::new(
+                    level,
+                    Start::new(1),
+                    NumberFormat::new(format),
+                    LevelText::new(text),
+                    LevelJc::new("left"),
+                )
+                .indent(
+                    Some(self.list_left_indent(level)),
+                    Some(SpecialIndentType::Hanging(self.list_indentation.hanging)),
+                    None,
+                    None,
+                ),
+            );
+        }
+        numbering
+    }
+
+    fn task_glyph(checked: bool) -> &'static str {
+        if checked { "☒" } else { "☐" }
     }
 
-    fn render_list(&self, list: &mdast::List, depth: usize) -> Vec<DocxParagraph> {
+    // First paragraph of a list item: carries the numbering (or the task checkbox)
+    fn list_item_paragraph(
+        &self,
+        list: &mdast::List,
+        item: &mdast::ListItem,
+        depth: usize,
+        numbering_id: usize,
+    ) -> DocxParagraph {
+        let para = self.apply_text_direction(DocxParagraph::new().indent(
+            Some(self.list_left_indent(depth)),
+            Some(SpecialIndentType::Hanging(self.list_indentation.hanging)),
+            None,
+            None,
+        ));
+        let numbering = (
+            NumberingId::new(numbering_id),
+            IndentLevel::new(Self::list_level(depth)),
+        );
+
+        match (item.checked, list.ordered) {
+            // Task items in bullet lists show the checkbox instead of the bullet
+            (Some(checked), false) => {
+                para.add_run(DocxRun::new().add_text(Self::task_glyph(checked)).add_tab())
+            }
+            // Ordered task items keep their number and get the checkbox after it
+            (Some(checked), true) => para
+                .numbering(numbering.0, numbering.1)
+                .add_run(DocxRun::new().add_text(format!("{} ", Self::task_glyph(checked)))),
+            (None, _) => para.numbering(numbering.0, numbering.1),
+        }
+    }
+
+    fn render_list(
+        &self,
+        list: &mdast::List,
+        depth: usize,
+        state: &mut RenderState,
+    ) -> Vec<DocxBlock> {
         let mut out = Vec::new();
-        let mut index = list.start.unwrap_or(1);
+        let start = list.start.unwrap_or(1) as usize;
+        let numbering_id = state.add_list_numbering(list.ordered, Self::list_level(depth), start);
+        // Left edge of the item text, after the bullet/number
+        let text_indent = self.list_left_indent(depth) + self.list_indentation.hanging;
 
         for item_node in &list.children {
             let Node::ListItem(item) = item_node else {
@@ -181,77 +1241,381 @@ impl DocxExporter {
             for child in &item.children {
                 match child {
                     Node::Paragraph(p) => {
-                        let mut para = DocxParagraph::new().indent(
-                            Some(Self::list_left_indent(depth)),
-                            Some(SpecialIndentType::Hanging(LIST_HANGING)),
-                            None,
-                            None,
-                        );
-
-                        if first_block {
-                            let marker = if list.ordered {
-                                format!("{}.", index)
-                            } else {
-                                "•".to_string()
-                            };
-                            para = para.add_run(DocxRun::new().bold().add_text(marker + " "));
+                        let mut para = if first_block {
+                            self.list_item_paragraph(list, item, depth, numbering_id)
                         } else {
-                            para = DocxParagraph::new().indent(
-                                Some(Self::list_left_indent(depth) + LIST_HANGING),
+                            // Continuation paragraphs align with the item text and carry no
+                            // number, so the count continues with the next item
+                            self.apply_text_direction(DocxParagraph::new().indent(
+                                Some(text_indent),
                                 None,
                                 None,
                                 None,
-                            );
+                            ))
+                        };
+                        if let Some(spacing) = self.body_line_spacing() {
+                            para = para.line_spacing(spacing);
                         }
 
                         para = self.append_inline_children_with_base(
                             para,
                             &p.children,
-                            false,
-                            false,
-                            0,
-                            false,
+                            InlineStyle::default(),
+                            state,
                         );
-                        out.push(para);
+                        out.push(para.into());
+                        out.extend(state.pending_captions.drain(..).map(DocxBlock::from));
                         first_block = false;
                     }
                     Node::List(nested) => {
-                        let nested_vec = self.render_list(nested, depth + 1);
+                        let nested_vec = self.render_list(nested, depth + 1, state);
                         out.extend(nested_vec);
                     }
                     other => {
-                        let blocks = self.render_block_node(other, depth + 1);
-                        out.extend(blocks);
+                        // Tables (e.g. code blocks) line up with the item text
+                        let blocks = self.render_block_node(other, depth + 1, state);
+                        out.extend(blocks.into_iter().map(|block| match block {
+                            DocxBlock::Table(table) => table.indent(text_indent).into(),
+                            other => other,
+                        }));
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    // ---------------- Blockquotes ----------------
+
+    fn render_blockquote(
+        &self,
+        bq: &mdast::Blockquote,
+        depth: usize,
+        state: &mut RenderState,
+    ) -> Vec<DocxBlock> {
+        let mut out = Vec::new();
+        for child in &bq.children {
+            for block in self.render_block_node(child, depth, state) {
+                out.push(match block {
+                    DocxBlock::Paragraph(para) => self.quote_paragraph(*para).into(),
+                    DocxBlock::Table(table) => table.indent(QUOTE_INDENT).into(),
+                });
+            }
+        }
+        out
+    }
+
+    // Shift an already rendered paragraph one quote level to the right, keeping whatever
+    // indentation it already has so nested quotes (and lists inside quotes) stack up.
+    fn quote_paragraph(&self, mut para: DocxParagraph) -> DocxParagraph {
+        let (start, special) = match &para.property.indent {
+            Some(indent) => (indent.start.unwrap_or(0), indent.special_indent),
+            None => (0, None),
+        };
+        para = para.indent(Some(start + QUOTE_INDENT), special, None, None);
+        para = Self::add_paragraph_border(
+            para,
+            ParagraphBorder::new(ParagraphBorderPosition::Left)
+                .val(BorderType::Single)
+                .size(QUOTE_BORDER_SIZE)
+                .space(QUOTE_BORDER_SPACE)
+                .color(QUOTE_BORDER_COLOR),
+        );
+
+        // Grey out plain runs only; hyperlinks and explicitly coloured runs keep their colour
+        para.children = para
+            .children
+            .into_iter()
+            .map(|child| match child {
+                ParagraphChild::Run(run) if run.run_property.color.is_none() => {
+                    ParagraphChild::Run(Box::new(run.color(QUOTE_TEXT_COLOR)))
+                }
+                other => other,
+            })
+            .collect();
+        para
+    }
+
+    // ---------------- Definition lists ----------------
+
+    // The parser has no definition lists, so
+    //
+    //     Term
+    //     : definition
+    //
+    // arrives as one paragraph with soft line breaks. Recognised when every line is either a
+    // term or starts with `: `, the first line is a term and the last one a definition.
+    fn definition_list_lines(p: &mdast::Paragraph) -> Option<Vec<DefinitionLine>> {
+        // Split the inline children into lines at the newlines inside text nodes
+        let mut lines: Vec<Vec<Node>> = vec![Vec::new()];
+        for child in &p.children {
+            let Node::Text(text) = child else {
+                lines.last_mut()?.push(child.clone());
+                continue;
+            };
+            for (index, part) in text.value.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(Vec::new());
+                }
+                if !part.is_empty() {
+                    lines.last_mut()?.push(Node::Text(mdast::Text {
+                        value: part.to_string(),
+                        position: None,
+                    }));
+                }
+            }
+        }
+        if lines.len() < 2 {
+            return None;
+        }
+
+        let mut result = Vec::with_capacity(lines.len());
+        for mut line in lines {
+            let definition = match line.first_mut() {
+                Some(Node::Text(text)) => match text.value.strip_prefix(": ") {
+                    Some(rest) => {
+                        text.value = rest.trim_start().to_string();
+                        true
                     }
+                    None => false,
+                },
+                Some(_) => false,
+                None => return None,
+            };
+            result.push(if definition {
+                DefinitionLine::Definition(line)
+            } else {
+                DefinitionLine::Term(line)
+            });
+        }
+
+        match (result.first(), result.last()) {
+            (Some(DefinitionLine::Term(_)), Some(DefinitionLine::Definition(_))) => Some(result),
+            _ => None,
+        }
+    }
+
+    // Terms as bold paragraphs, definitions indented beneath them
+    fn render_definition_list(
+        &self,
+        lines: Vec<DefinitionLine>,
+        state: &mut RenderState,
+    ) -> Vec<DocxBlock> {
+        lines
+            .into_iter()
+            .map(|line| {
+                let para = match line {
+                    DefinitionLine::Term(nodes) => self.append_inline_children_with_base(
+                        self.new_body_paragraph().keep_next(true),
+                        &nodes,
+                        InlineStyle {
+                            bold: true,
+                            ..InlineStyle::default()
+                        },
+                        state,
+                    ),
+                    DefinitionLine::Definition(nodes) => self.append_inline_children_with_base(
+                        self.new_body_paragraph()
+                            .indent(Some(DEFINITION_INDENT), None, None, None),
+                        &nodes,
+                        InlineStyle::default(),
+                        state,
+                    ),
+                };
+                para.into()
+            })
+            .collect()
+    }
+
+    // ---------------- Thematic breaks ----------------
+
+    // An empty paragraph whose bottom border spans the full content width
+    fn render_thematic_break(&self) -> DocxParagraph {
+        let para = DocxParagraph::new()
+            .indent(Some(0), None, Some(0), None)
+            .line_spacing(
+                docx_rs::LineSpacing::new()
+                    .before(RULE_SPACING)
+                    .after(RULE_SPACING),
+            );
+        Self::add_paragraph_border(
+            para,
+            ParagraphBorder::new(ParagraphBorderPosition::Bottom)
+                .val(BorderType::Single)
+                .size(RULE_BORDER_SIZE)
+                .space(1)
+                .color("auto"),
+        )
+    }
+
+    // ---------------- Tables ----------------
+
+    fn table_cell_alignment(align: Option<&mdast::AlignKind>) -> Option<AlignmentType> {
+        match align {
+            Some(mdast::AlignKind::Left) => Some(AlignmentType::Left),
+            Some(mdast::AlignKind::Center) => Some(AlignmentType::Center),
+            Some(mdast::AlignKind::Right) => Some(AlignmentType::Right),
+            Some(mdast::AlignKind::None) | None => None,
+        }
+    }
+
+    // First row is the GFM header row and gets bold text; borders, header shading and the
+    // repeated header row come from the configured `TableStyle`.
+    fn render_table(&self, table: &mdast::Table, state: &mut RenderState) -> DocxTable {
+        let mut rows = Vec::new();
+        let footnote_fallback = std::mem::replace(&mut state.footnote_fallback, true);
+
+        for (row_index, row_node) in table.children.iter().enumerate() {
+            let Node::TableRow(row) = row_node else {
+                continue;
+            };
+            // A header row of empty cells is how Markdown spells a headerless table
+            let is_header = row_index == 0
+                && row
+                    .children
+                    .iter()
+                    .any(|cell| cell.children().is_some_and(|children| !children.is_empty()));
+
+            let mut cells = Vec::new();
+            for (col_index, cell_node) in row.children.iter().enumerate() {
+                let Node::TableCell(cell) = cell_node else {
+                    continue;
+                };
+                let mut para = self.apply_text_direction(DocxParagraph::new());
+                if let Some(align) = Self::table_cell_alignment(table.align.get(col_index)) {
+                    para = para.align(align);
+                }
+                para = self.append_inline_children_with_base(
+                    para,
+                    &cell.children,
+                    InlineStyle {
+                        bold: is_header,
+                        ..InlineStyle::default()
+                    },
+                    state,
+                );
+                let mut cell = DocxTableCell::new()
+                    .set_borders(self.table_cell_borders(is_header))
+                    .add_paragraph(para);
+                if is_header && let Some(fill) = &self.table_style.header_fill {
+                    cell = cell.shading(
+                        Shading::new()
+                            .shd_type(ShdType::Clear)
+                            .color("auto")
+                            .fill(fill),
+                    );
+                }
+                for caption in state.pending_captions.drain(..) {
+                    cell = cell.add_paragraph(caption);
                 }
+                cells.push(cell);
+            }
+            let mut row = DocxTableRow::new(cells);
+            if is_header && self.table_style.repeat_header {
+                row.property = row.property.cant_split();
             }
+            rows.push(row);
+        }
+        state.footnote_fallback = footnote_fallback;
+
+        DocxTable::new(rows)
+    }
+
+    fn table_cell_borders(&self, is_header: bool) -> TableCellBorders {
+        let style = &self.table_style;
+        let border = |position: TableCellBorderPosition, size: usize| {
+            TableCellBorder::new(position)
+                .border_type(BorderType::Single)
+                .size(size)
+                .color(&style.border_color)
+        };
+        let bottom_size = if is_header {
+            style.header_border_size
+        } else {
+            style.border_size
+        };
+        TableCellBorders::with_empty()
+            .set(border(TableCellBorderPosition::Top, style.border_size))
+            .set(border(TableCellBorderPosition::Left, style.border_size))
+            .set(border(TableCellBorderPosition::Right, style.border_size))
+            .set(border(TableCellBorderPosition::Bottom, bottom_size))
+    }
 
-            if list.ordered {
-                index += 1;
-            }
-        }
+    // ---------------- Table of contents ----------------
 
-        out
+    // A dirty TOC field so Word offers to build it from the heading styles on open
+    fn render_toc(&self) -> TableOfContents {
+        let first = (*self.toc_levels.start()).clamp(1, 6);
+        let last = (*self.toc_levels.end()).clamp(first, 6);
+        TableOfContents::new()
+            .heading_styles_range(first, last)
+            .hyperlink()
+            .alias("Table of contents")
+            .dirty()
+    }
+
+    // ---------------- Header / footer ----------------
+
+    fn render_header(&self) -> Option<Header> {
+        let text = self.header_text.as_deref()?;
+        let para = self.add_text_run(
+            self.apply_text_direction(DocxParagraph::new().align(AlignmentType::Center)),
+            text,
+            InlineStyle::default(),
+        );
+        Some(Header::new().add_paragraph(para))
+    }
+
+    // PAGE / NUMPAGES fields are filled in by Word when the document is laid out
+    fn render_footer(&self) -> Option<Footer> {
+        if !self.footer_page_numbers {
+            return None;
+        }
+        let mut para = self.apply_text_direction(DocxParagraph::new().align(AlignmentType::Center));
+        para = self.add_text_run(para, "Page ", InlineStyle::default());
+        para = para.add_page_num(PageNum::new());
+        para = self.add_text_run(para, " of ", InlineStyle::default());
+        para = para.add_num_pages(NumPages::new());
+        Some(Footer::new().add_paragraph(para))
     }
 
     // ---------------- Block dispatcher ----------------
 
-    fn render_block_node(&self, node: &Node, depth: usize) -> Vec<DocxParagraph> {
-        match node {
+    fn render_block_node(
+        &self,
+        node: &Node,
+        depth: usize,
+        state: &mut RenderState,
+    ) -> Vec<DocxBlock> {
+        let mut blocks = match node {
             Node::Paragraph(p) => {
-                if let Some((heading, rest)) = self.split_paragraph_heading(p) {
-                    let mut v = vec![heading];
+                if let Some(lines) = Self::definition_list_lines(p) {
+                    self.render_definition_list(lines, state)
+                } else if let Some((heading, rest)) = self.split_paragraph_heading(p, state) {
+                    let mut v = vec![heading.into()];
                     if let Some(r) = rest {
-                        v.push(r);
+                        v.push(r.into());
                     }
                     v
                 } else {
-                    vec![self.render_paragraph(p)]
+                    vec![self.render_paragraph(p, state).into()]
                 }
             }
-            Node::Heading(h) => vec![self.render_heading_node(h)],
-            Node::Code(code_block) => vec![self.render_code_block(code_block)],
-            Node::List(list) => self.render_list(list, depth),
+            Node::Heading(h) => vec![self.render_heading_node(h, state).into()],
+            Node::Code(code_block) => {
+                let mut v = Vec::new();
+                if let Some(label) = self.render_code_language(code_block) {
+                    v.push(label.into());
+                }
+                v.push(self.render_code_block(code_block).into());
+                v
+            }
+            Node::List(list) => self.render_list(list, depth, state),
+            Node::Blockquote(bq) => self.render_blockquote(bq, depth, state),
+            Node::ThematicBreak(_) => vec![self.render_thematic_break().into()],
+            Node::Table(table) => vec![self.render_table(table, state).into()],
+            Node::Html(html) => self.render_html_block(html).into_iter().collect(),
             Node::Text(_)
             | Node::Strong(_)
             | Node::Emphasis(_)
@@ -261,45 +1625,115 @@ impl DocxExporter {
                 para = self.append_inline_children_with_base(
                     para,
                     std::slice::from_ref(node),
-                    false,
-                    false,
-                    0,
-                    false,
+                    InlineStyle::default(),
+                    state,
                 );
-                vec![para]
+                vec![para.into()]
             }
-            _ => Vec::new(),
-        }
+            // Gathered up front and rendered where they are referenced
+            Node::FootnoteDefinition(_) | Node::Definition(_) => Vec::new(),
+            other => {
+                state.warn_dropped(other);
+                Vec::new()
+            }
+        };
+        blocks.extend(state.pending_captions.drain(..).map(DocxBlock::from));
+        blocks
     }
 
-    fn render_paragraph(&self, p: &mdast::Paragraph) -> DocxParagraph {
+    fn render_paragraph(&self, p: &mdast::Paragraph, state: &mut RenderState) -> DocxParagraph {
         let mut para = self.new_body_paragraph();
-        para = self.append_inline_children_with_base(para, &p.children, false, false, 0, false);
+        para =
+            self.append_inline_children_with_base(para, &p.children, InlineStyle::default(), state);
         para
     }
 
-    fn render_code_block(&self, code: &mdast::Code) -> DocxParagraph {
-        let mut p = self.new_body_paragraph();
-        p = p.indent(Some(0), None, None, None);
+    // Code blocks are a single shaded, bordered table cell so the background stays one
+    // contiguous block across all lines (docx_rs has no paragraph shading).
+    fn render_code_block(&self, code: &mdast::Code) -> DocxTable {
+        let border = |position| {
+            TableBorder::new(position)
+                .size(CODE_BORDER_SIZE)
+                .color(CODE_BORDER_COLOR)
+        };
+        let cell = DocxTableCell::new()
+            .shading(
+                Shading::new()
+                    .shd_type(ShdType::Clear)
+                    .color("auto")
+                    .fill(&self.code_block_fill),
+            )
+            .add_paragraph(self.render_code_paragraph(code));
+
+        DocxTable::new(vec![DocxTableRow::new(vec![cell])])
+            .width(FULL_WIDTH_PCT, WidthType::Pct)
+            .set_borders(
+                TableBorders::with_empty()
+                    .set(border(TableBorderPosition::Top))
+                    .set(border(TableBorderPosition::Left))
+                    .set(border(TableBorderPosition::Bottom))
+                    .set(border(TableBorderPosition::Right)),
+            )
+    }
+
+    // Language tag kept on the same page as the block it labels
+    fn render_code_language(&self, code: &mdast::Code) -> Option<DocxParagraph> {
+        let lang = code
+            .lang
+            .as_deref()
+            .map(str::trim)
+            .filter(|lang| !lang.is_empty())?;
+        if !self.show_code_language {
+            return None;
+        }
+        let para = DocxParagraph::new().keep_next(true).line_spacing(
+            docx_rs::LineSpacing::new()
+                .before(0)
+                .after(CODE_LABEL_SPACING),
+        );
+        Some(self.add_text_run(
+            para,
+            &lang.to_uppercase(),
+            InlineStyle {
+                bold: true,
+                size: (self.body_font_size() * 4 / 5).max(2),
+                ..InlineStyle::default()
+            },
+        ))
+    }
+
+    fn render_code_paragraph(&self, code: &mdast::Code) -> DocxParagraph {
+        let mut p = DocxParagraph::new();
+        // `split` rather than `lines()` so a trailing newline keeps its final blank line
+        let lines: Vec<&str> = code
+            .value
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect();
+        let number_width = lines.len().to_string().len();
 
         // Split code by newlines and create runs with breaks
-        for (i, line) in code.value.lines().enumerate() {
-            let mut run = DocxRun::new()
-                .fonts(
-                    RunFonts::new()
-                        .ascii(&self.mono_font_family)
-                        .hi_ansi(&self.mono_font_family),
-                )
-                .add_text(line.to_string());
+        for (i, line) in lines.iter().enumerate() {
+            if self.code_line_numbers {
+                // Right-aligned by padding to the widest number; greyed so it reads as gutter
+                let number = DocxRun::new()
+                    .fonts(self.run_fonts(&self.mono_font_family))
+                    .color(CODE_LINE_NUMBER_COLOR)
+                    .add_text(format!("{:>number_width$} ", i + 1))
+                    .size(self.body_font_size());
+                p = p.add_run(number);
+            }
 
-            if self.default_font_size > 0 {
-                run = run.size(self.default_font_size);
+            // Blank lines still get a (text-less) run so the break has a line to end
+            let mut run = DocxRun::new().fonts(self.run_fonts(&self.mono_font_family));
+            if !line.is_empty() {
+                run = run.add_text(*line);
             }
 
-            p = p.add_run(run);
+            p = p.add_run(run.size(self.body_font_size()));
 
             // Add line break after each line except the last
-            if i < code.value.lines().count() - 1 {
+            if i + 1 < lines.len() {
                 let break_run = DocxRun::new().add_break(BreakType::TextWrapping);
                 p = p.add_run(break_run);
             }
@@ -314,144 +1748,789 @@ impl DocxExporter {
         &self,
         mut paragraph: DocxParagraph,
         nodes: &[Node],
-        force_bold: bool,
-        force_italic: bool,
-        base_size: usize,
-        mono: bool,
+        base_style: InlineStyle,
+        state: &mut RenderState,
     ) -> DocxParagraph {
-        for node in nodes {
+        // Inline HTML arrives as separate sibling nodes (`<b>`, text, `</b>`), so the
+        // formatting it opens is tracked across the whole run of siblings. The same goes for
+        // `==highlight==`, whose delimiters may sit in different text nodes (`==**x**==`).
+        let mut html_spans = HtmlSpans::default();
+        let highlight_marks = Self::highlight_marks(nodes);
+        let mut highlighted = false;
+        for (index, node) in nodes.iter().enumerate() {
+            let mut style = html_spans.apply(base_style);
+            style.highlight |= highlighted;
             match node {
                 Node::Text(t) => {
-                    let mut parts = t.value.split('\n').peekable();
-                    while let Some(part) = parts.next() {
-                        if !part.is_empty() {
-                            paragraph = self.add_text_run(
-                                paragraph,
-                                part,
-                                force_bold,
-                                force_italic,
-                                mono,
-                                base_size,
-                            );
-                        }
-                        if parts.peek().is_some() {
-                            paragraph = paragraph
-                                .add_run(DocxRun::new().add_break(BreakType::TextWrapping));
-                        }
+                    let mut start = 0;
+                    for &(_, mark) in highlight_marks.iter().filter(|(i, _)| *i == index) {
+                        paragraph =
+                            self.append_text(paragraph, &t.value[start..mark], style, state);
+                        highlighted = !highlighted;
+                        style.highlight = base_style.highlight || highlighted;
+                        start = mark + HIGHLIGHT_DELIMITER.len();
                     }
+                    paragraph = self.append_text(paragraph, &t.value[start..], style, state);
                 }
                 Node::InlineCode(ic) => {
                     paragraph = self.add_text_run(
                         paragraph,
                         &ic.value,
-                        force_bold,
-                        force_italic,
-                        true,
-                        base_size,
+                        InlineStyle {
+                            mono: true,
+                            ..style
+                        },
                     );
                 }
                 Node::Code(c) => {
                     paragraph = self.add_text_run(
                         paragraph,
                         &c.value,
-                        force_bold,
-                        force_italic,
-                        true,
-                        base_size,
+                        InlineStyle {
+                            mono: true,
+                            ..style
+                        },
                     );
                 }
                 Node::Emphasis(em) => {
                     paragraph = self.append_inline_children_with_base(
                         paragraph,
                         &em.children,
-                        force_bold,
-                        true,
-                        base_size,
-                        mono,
+                        InlineStyle {
+                            italic: true,
+                            ..style
+                        },
+                        state,
                     );
                 }
                 Node::Strong(st) => {
                     paragraph = self.append_inline_children_with_base(
                         paragraph,
                         &st.children,
-                        true,
-                        force_italic,
-                        base_size,
-                        mono,
+                        InlineStyle {
+                            bold: true,
+                            ..style
+                        },
+                        state,
+                    );
+                }
+                Node::Delete(del) => {
+                    paragraph = self.append_inline_children_with_base(
+                        paragraph,
+                        &del.children,
+                        InlineStyle {
+                            strike: true,
+                            ..style
+                        },
+                        state,
                     );
                 }
                 Node::Break(_) => {
                     paragraph =
                         paragraph.add_run(DocxRun::new().add_break(BreakType::TextWrapping));
                 }
+                Node::Image(image) => {
+                    paragraph = self.append_image(paragraph, image, style, state);
+                }
+                Node::Link(link) => {
+                    paragraph = paragraph.add_hyperlink(self.render_link(link, style, state));
+                }
+                // `[text][label]` / `![alt][label]`: the parser only creates them for
+                // labels that are defined, but the definition may come later
+                Node::LinkReference(reference) => {
+                    paragraph = match state.link_definitions.get(&reference.identifier) {
+                        Some(definition) => {
+                            let link = mdast::Link {
+                                children: reference.children.clone(),
+                                url: definition.url.clone(),
+                                title: definition.title.clone(),
+                                position: None,
+                            };
+                            paragraph.add_hyperlink(self.render_link(&link, style, state))
+                        }
+                        None => self.append_inline_children_with_base(
+                            paragraph,
+                            &reference.children,
+                            style,
+                            state,
+                        ),
+                    };
+                }
+                Node::ImageReference(reference) => {
+                    let image = mdast::Image {
+                        alt: reference.alt.clone(),
+                        url: state
+                            .link_definitions
+                            .get(&reference.identifier)
+                            .map(|definition| definition.url.clone())
+                            .unwrap_or_default(),
+                        title: None,
+                        position: None,
+                    };
+                    paragraph = self.append_image(paragraph, &image, style, state);
+                }
+                Node::Html(html) => {
+                    paragraph =
+                        self.append_html(paragraph, &html.value, base_style, &mut html_spans);
+                }
+                Node::FootnoteReference(reference) => {
+                    paragraph = self.render_footnote_reference(paragraph, reference, style, state);
+                }
                 other => {
                     let txt = self.collect_plain_text(std::slice::from_ref(other));
-                    if !txt.is_empty() {
-                        paragraph = self.add_text_run(
-                            paragraph,
-                            &txt,
-                            force_bold,
-                            force_italic,
-                            mono,
-                            base_size,
-                        );
+                    if txt.is_empty() {
+                        state.warn_dropped(other);
+                    } else {
+                        paragraph = self.add_text_run(paragraph, &txt, style);
+                    }
+                }
+            }
+        }
+        paragraph
+    }
+
+    // The picture, with its caption queued for after the paragraph; an image that cannot
+    // be loaded shows its fallback text in italics
+    fn append_image(
+        &self,
+        paragraph: DocxParagraph,
+        image: &mdast::Image,
+        style: InlineStyle,
+        state: &mut RenderState,
+    ) -> DocxParagraph {
+        match self.render_image(image) {
+            Some(pic) => {
+                if let Some(caption) = self.render_image_caption(image, state) {
+                    state.pending_captions.push(caption);
+                }
+                paragraph.add_run(DocxRun::new().add_image(pic))
+            }
+            None => {
+                let fallback = state.image_fallback(image);
+                self.add_text_run(
+                    paragraph,
+                    &fallback,
+                    InlineStyle {
+                        italic: true,
+                        ..style
+                    },
+                )
+            }
+        }
+    }
+
+    // ---------------- Raw HTML ----------------
+
+    // Block-level HTML becomes a body paragraph holding the text content; fragments with
+    // no text (comments, lone closing tags) produce nothing.
+    fn render_html_block(&self, html: &mdast::Html) -> Option<DocxBlock> {
+        let para = self.append_html(
+            self.new_body_paragraph(),
+            html.value.trim(),
+            InlineStyle::default(),
+            &mut HtmlSpans::default(),
+        );
+        (!para.children.is_empty()).then(|| para.into())
+    }
+
+    // `<br>` becomes a line break and known formatting tags restyle the following text;
+    // every other tag is dropped while its text content is kept.
+    fn append_html(
+        &self,
+        mut paragraph: DocxParagraph,
+        html: &str,
+        base_style: InlineStyle,
+        spans: &mut HtmlSpans,
+    ) -> DocxParagraph {
+        for token in Self::tokenize_html(html) {
+            match token {
+                HtmlToken::Tag { name, closing } => {
+                    if name == "br" {
+                        paragraph =
+                            paragraph.add_run(DocxRun::new().add_break(BreakType::TextWrapping));
+                    } else {
+                        spans.toggle(&name, closing);
+                    }
+                }
+                // Whitespace between block tags is source formatting, not content
+                HtmlToken::Text(text) if text.trim().is_empty() && text.contains('\n') => {}
+                HtmlToken::Text(text) => {
+                    let text = Self::decode_html_entities(&Self::collapse_whitespace(text));
+                    paragraph = self.add_text_run(paragraph, &text, spans.apply(base_style));
+                }
+            }
+        }
+        paragraph
+    }
+
+    // Lenient scanner: comments are skipped and a `<` that never closes is kept as text,
+    // so malformed HTML degrades to plain text instead of failing the export.
+    fn tokenize_html(html: &str) -> Vec<HtmlToken<'_>> {
+        let mut tokens = Vec::new();
+        let mut rest = html;
+        while let Some(open) = rest.find('<') {
+            if open > 0 {
+                tokens.push(HtmlToken::Text(&rest[..open]));
+            }
+            let tag = &rest[open..];
+            if let Some(comment) = tag.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            let Some(close) = tag.find('>') else {
+                tokens.push(HtmlToken::Text(tag));
+                return tokens;
+            };
+            let inner = tag[1..close].trim();
+            let (closing, inner) = match inner.strip_prefix('/') {
+                Some(inner) => (true, inner.trim_start()),
+                None => (false, inner),
+            };
+            let name: String = inner
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .collect();
+            if name.is_empty() {
+                // Not a tag after all (e.g. `a < b > c`)
+                tokens.push(HtmlToken::Text(&tag[..=close]));
+            } else {
+                tokens.push(HtmlToken::Tag {
+                    name: name.to_ascii_lowercase(),
+                    closing,
+                });
+            }
+            rest = &tag[close + 1..];
+        }
+        if !rest.is_empty() {
+            tokens.push(HtmlToken::Text(rest));
+        }
+        tokens
+    }
+
+    fn collapse_whitespace(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last_space = false;
+        for ch in text.chars() {
+            if ch.is_whitespace() {
+                if !last_space {
+                    out.push(' ');
+                }
+                last_space = true;
+            } else {
+                out.push(ch);
+                last_space = false;
+            }
+        }
+        out
+    }
+
+    // The common named entities plus numeric references; unknown ones stay verbatim
+    fn decode_html_entities(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(amp) = rest.find('&') {
+            out.push_str(&rest[..amp]);
+            let candidate = &rest[amp..];
+            let decoded = candidate.find(';').and_then(|end| {
+                let entity = &candidate[1..end];
+                let ch = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{00A0}'),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                        .map(|hex| u32::from_str_radix(hex, 16))
+                        .or_else(|| entity.strip_prefix('#').map(str::parse))
+                        .and_then(Result::ok)
+                        .and_then(char::from_u32),
+                };
+                ch.map(|ch| (ch, end))
+            });
+            match decoded {
+                Some((ch, end)) => {
+                    out.push(ch);
+                    rest = &candidate[end + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &candidate[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    // ---------------- Footnotes ----------------
+
+    // Superscript character style used for the note markers in the body text
+    fn footnote_reference_style() -> Style {
+        let mut style = Style::new(FOOTNOTE_REFERENCE_STYLE, StyleType::Character)
+            .name("footnote reference")
+            .based_on("DefaultParagraphFont");
+        style.run_property = style.run_property.vert_align(VertAlignType::SuperScript);
+        style
+    }
+
+    // Word numbers the notes itself; the reference run only points at the footnote whose
+    // content is rendered from the matching definition.
+    fn render_footnote_reference(
+        &self,
+        paragraph: DocxParagraph,
+        reference: &mdast::FootnoteReference,
+        style: InlineStyle,
+        state: &mut RenderState,
+    ) -> DocxParagraph {
+        let definition = if state.footnote_fallback {
+            None
+        } else {
+            state
+                .footnote_definitions
+                .get(&reference.identifier)
+                .cloned()
+        };
+        let Some(children) = definition else {
+            let label = reference.label.as_deref().unwrap_or(&reference.identifier);
+            let mut run = DocxRun::new()
+                .add_text(label)
+                .style(FOOTNOTE_REFERENCE_STYLE);
+            if style.size > 0 {
+                run = run.size(style.size);
+            }
+            return paragraph.add_run(run);
+        };
+
+        // References inside the note itself are not collected by docx_rs either
+        let footnote_fallback = std::mem::replace(&mut state.footnote_fallback, true);
+        // Captions of the surrounding paragraph must not end up inside the note
+        let pending_captions = std::mem::take(&mut state.pending_captions);
+        let mut footnote = Footnote::new();
+        for child in &children {
+            for block in self.render_block_node(child, 0, state) {
+                // Word footnotes hold paragraphs only; tables inside a note are dropped
+                if let DocxBlock::Paragraph(para) = block {
+                    footnote = footnote.add_content(*para);
+                }
+            }
+        }
+        state.footnote_fallback = footnote_fallback;
+        state.pending_captions = pending_captions;
+
+        paragraph.add_run(DocxRun::new().add_footnote_reference(footnote))
+    }
+
+    // ---------------- Images ----------------
+
+    // Resolve, decode and scale an image; `None` when any step fails so the caller can
+    // fall back to the alt text.
+    fn render_image(&self, image: &mdast::Image) -> Option<Pic> {
+        let bytes = match image.url.strip_prefix("data:") {
+            Some(data_uri) => Self::decode_data_uri(data_uri)?,
+            None => self.image_resolver.as_ref()?(&image.url)?,
+        };
+
+        // Re-encode as PNG so any format the `image` crate understands can be embedded
+        let decoded = image::load_from_memory(&bytes).ok()?;
+        let mut png = Cursor::new(Vec::new());
+        decoded.write_to(&mut png, image::ImageFormat::Png).ok()?;
+
+        let (width_px, height_px) = (decoded.width(), decoded.height());
+        if width_px == 0 || height_px == 0 {
+            return None;
+        }
+        let (display_width, display_height) = match Self::split_size_hint(&image.alt).1 {
+            // One dimension given: keep the aspect ratio
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => (width, scale_dimension(height_px, width, width_px)),
+            (None, Some(height)) => (scale_dimension(width_px, height, height_px), height),
+            (None, None) => (width_px, height_px),
+        };
+        let (width, height) = self.scale_image(display_width, display_height);
+        Some(Pic::new_with_dimensions(png.into_inner(), width_px, height_px).size(width, height))
+    }
+
+    fn render_image_caption(
+        &self,
+        image: &mdast::Image,
+        state: &mut RenderState,
+    ) -> Option<DocxParagraph> {
+        let alt = Self::split_size_hint(&image.alt).0.trim();
+        if !self.caption_images || alt.is_empty() {
+            return None;
+        }
+        let text = if self.number_figures {
+            state.figure_count += 1;
+            format!("Figure {}: {alt}", state.figure_count)
+        } else {
+            alt.to_string()
+        };
+        Some(self.add_text_run(
+            self.new_body_paragraph().align(AlignmentType::Center),
+            &text,
+            InlineStyle {
+                italic: true,
+                ..InlineStyle::default()
+            },
+        ))
+    }
+
+    // Inline `data:image/png;base64,...` images carry their own bytes, no resolver needed.
+    // Only base64 payloads with an image media type the `image` crate can decode qualify.
+    fn decode_data_uri(data_uri: &str) -> Option<Vec<u8>> {
+        let (header, payload) = data_uri.split_once(',')?;
+        let media_type = header.strip_suffix(";base64")?;
+        image::ImageFormat::from_mime_type(media_type)?;
+        let payload: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+        BASE64_STANDARD.decode(payload).ok()
+    }
+
+    // Display size hint at the end of the alt text: `![chart{width=300}](...)` or
+    // `![chart {width=300 height=200}](...)`, in pixels (96 dpi, optional `px`). Returns the
+    // alt text without the hint and the (width, height) it requested; zero or malformed
+    // values are ignored.
+    fn split_size_hint(alt: &str) -> (&str, (Option<u32>, Option<u32>)) {
+        let Some(open) = alt.rfind('{') else {
+            return (alt, (None, None));
+        };
+        let Some(hint) = alt[open + 1..].trim_end().strip_suffix('}') else {
+            return (alt, (None, None));
+        };
+
+        let (mut width, mut height) = (None, None);
+        for attribute in hint.split([' ', ',']).filter(|a| !a.is_empty()) {
+            let Some((key, value)) = attribute.split_once('=') else {
+                return (alt, (None, None));
+            };
+            let value = value
+                .trim_end_matches("px")
+                .parse::<u32>()
+                .ok()
+                .filter(|v| *v > 0);
+            match key {
+                "width" => width = value,
+                "height" => height = value,
+                _ => return (alt, (None, None)),
+            }
+        }
+        (alt[..open].trim_end(), (width, height))
+    }
+
+    // Display pixel size in EMUs, scaled down proportionally to fit `max_image_width`
+    fn scale_image(&self, width_px: u32, height_px: u32) -> (u32, u32) {
+        let width = width_px as u64 * EMU_PER_PIXEL;
+        let height = height_px as u64 * EMU_PER_PIXEL;
+        let max_width = self.max_image_width as u64;
+        if max_width == 0 || width <= max_width {
+            return (width as u32, height as u32);
+        }
+        (max_width as u32, (height * max_width / width) as u32)
+    }
+
+    // Display runs are rendered into a scratch paragraph, then restyled and moved into the link.
+    // An empty link text falls back to showing the URL itself.
+    fn render_link(
+        &self,
+        link: &mdast::Link,
+        style: InlineStyle,
+        state: &mut RenderState,
+    ) -> Hyperlink {
+        let footnote_fallback = std::mem::replace(&mut state.footnote_fallback, true);
+        let in_link = std::mem::replace(&mut state.in_link, true);
+        let mut scratch = self.append_inline_children_with_base(
+            DocxParagraph::new(),
+            &link.children,
+            style,
+            state,
+        );
+        state.footnote_fallback = footnote_fallback;
+        state.in_link = in_link;
+        if scratch.children.is_empty() {
+            scratch = self.add_text_run(scratch, &link.url, style);
+        }
+
+        // `#slug` targets the bookmark of the matching heading
+        let mut hyperlink = match link.url.strip_prefix('#') {
+            Some(slug) => {
+                let name = match state.heading_bookmarks.get(slug) {
+                    Some(name) => name.clone(),
+                    None => bookmark_name(slug),
+                };
+                Hyperlink::new(name, HyperlinkType::Anchor)
+            }
+            None => Hyperlink::new(&link.url, HyperlinkType::External),
+        };
+        for child in scratch.children {
+            if let ParagraphChild::Run(run) = child {
+                let mut run = *run;
+                if let Some(color) = &self.link_color {
+                    run = run.color(color);
+                }
+                if self.link_underline {
+                    run = run.underline("single");
+                }
+                hyperlink = hyperlink.add_run(run);
+            }
+        }
+        hyperlink
+    }
+
+    // Plain text: newlines become line breaks, bare URLs (if enabled) links, and `^`/`~`
+    // spans super/subscript
+    fn append_text(
+        &self,
+        mut paragraph: DocxParagraph,
+        text: &str,
+        style: InlineStyle,
+        state: &mut RenderState,
+    ) -> DocxParagraph {
+        let mut parts = text.split('\n').peekable();
+        while let Some(part) = parts.next() {
+            // Link text (and so every URL) stays literal: `~` and `^` are common in URLs
+            if state.in_link {
+                paragraph = self.add_text_run(paragraph, part, style);
+            } else {
+                for (segment, is_url) in self.split_bare_urls(part) {
+                    paragraph = if is_url {
+                        paragraph.add_hyperlink(self.render_link(
+                            &Self::bare_url_link(segment),
+                            style,
+                            state,
+                        ))
+                    } else {
+                        self.add_script_text(paragraph, segment, style)
+                    };
+                }
+            }
+            if parts.peek().is_some() {
+                paragraph = paragraph.add_run(DocxRun::new().add_break(BreakType::TextWrapping));
+            }
+        }
+        paragraph
+    }
+
+    // `(sibling index, byte offset)` of every `==` that is part of a `==highlight==` pair.
+    // An opener must be followed and a closer preceded by non-whitespace, so comparisons
+    // like `a == b` stay literal; an opener without a closer is ignored.
+    fn highlight_marks(nodes: &[Node]) -> Vec<(usize, usize)> {
+        let mut marks = Vec::new();
+        let mut open = None;
+        for (index, node) in nodes.iter().enumerate() {
+            let Node::Text(text) = node else {
+                continue;
+            };
+            let value = &text.value;
+            let mut pos = 0;
+            while let Some(offset) = value[pos..].find(HIGHLIGHT_DELIMITER) {
+                let mark = pos + offset;
+                pos = mark + HIGHLIGHT_DELIMITER.len();
+                let before = value[..mark].chars().next_back();
+                let after = value[pos..].chars().next();
+                // At the end of a text node the next sibling (e.g. `**bold**`) follows
+                let can_open = match after {
+                    Some(c) => !c.is_whitespace() && c != '=',
+                    None => index + 1 < nodes.len(),
+                };
+                let can_close = before.is_none_or(|c| !c.is_whitespace());
+                match open {
+                    Some(opener) if can_close => {
+                        marks.push(opener);
+                        marks.push((index, mark));
+                        open = None;
                     }
+                    _ if can_open => open = Some((index, mark)),
+                    _ => {}
                 }
             }
         }
+        marks
+    }
+
+    fn add_script_text(
+        &self,
+        mut paragraph: DocxParagraph,
+        text: &str,
+        style: InlineStyle,
+    ) -> DocxParagraph {
+        for (span, vert_align) in Self::split_script_spans(text) {
+            paragraph = self.add_text_run(
+                paragraph,
+                span,
+                InlineStyle {
+                    vert_align: vert_align.or(style.vert_align),
+                    ..style
+                },
+            );
+        }
         paragraph
     }
 
+    // With `autolink_urls`, bare `http(s)://` and `www.` URLs inside plain text are split
+    // out as link segments (`true`). Trailing sentence punctuation and unbalanced closing
+    // parentheses stay outside the link.
+    fn split_bare_urls<'a>(&self, text: &'a str) -> Vec<(&'a str, bool)> {
+        if !self.autolink_urls {
+            return vec![(text, false)];
+        }
+        let mut segments = Vec::new();
+        let mut plain_start = 0;
+        let mut pos = 0;
+        while let Some(start) = ["https://", "http://", "www."]
+            .iter()
+            .filter_map(|prefix| text[pos..].find(prefix).map(|offset| pos + offset))
+            .min()
+        {
+            // Only at a word boundary, so e.g. `foowww.x` is left alone
+            let boundary = text[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | '[' | '"' | '\''));
+            let end = text[start..]
+                .find(|c: char| c.is_whitespace() || c == '<')
+                .map_or(text.len(), |offset| start + offset);
+            let url = Self::trim_url_end(&text[start..end]);
+            // A bare scheme or `www.` with nothing after it is not a link
+            if !boundary || !url.trim_end_matches('/').contains('.') || url.ends_with("://") {
+                pos = start + 1;
+                continue;
+            }
+            segments.push((&text[plain_start..start], false));
+            segments.push((url, true));
+            pos = start + url.len();
+            plain_start = pos;
+        }
+        segments.push((&text[plain_start..], false));
+        segments.retain(|(segment, _)| !segment.is_empty());
+        segments
+    }
+
+    fn trim_url_end(mut url: &str) -> &str {
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ':', ';', '!', '?', '"', '\'']);
+            let trimmed = match trimmed.strip_suffix(')') {
+                Some(inner) if inner.matches('(').count() < trimmed.matches(')').count() => inner,
+                _ => trimmed,
+            };
+            if trimmed.len() == url.len() {
+                return url;
+            }
+            url = trimmed;
+        }
+    }
+
+    fn bare_url_link(url: &str) -> mdast::Link {
+        let href = if url.starts_with("www.") {
+            format!("http://{url}")
+        } else {
+            url.to_string()
+        };
+        mdast::Link {
+            children: vec![Node::Text(mdast::Text {
+                value: url.to_string(),
+                position: None,
+            })],
+            position: None,
+            url: href,
+            title: None,
+        }
+    }
+
+    // Pandoc-style `^sup^` and `~sub~` spans inside a text node. The content between the
+    // delimiters must be non-empty and free of whitespace; anything else stays literal.
+    fn split_script_spans(text: &str) -> Vec<(&str, Option<VertAlignType>)> {
+        let mut spans = Vec::new();
+        let mut plain_start = 0;
+        let mut pos = 0;
+        while let Some(offset) = text[pos..].find(['^', '~']) {
+            let open = pos + offset;
+            let delimiter = text[open..].chars().next().unwrap_or('^');
+            let content_start = open + delimiter.len_utf8();
+            let close = text[content_start..]
+                .find(delimiter)
+                .map(|offset| content_start + offset);
+            match close {
+                Some(close)
+                    if close > content_start
+                        && !text[content_start..close].contains(char::is_whitespace) =>
+                {
+                    let vert_align = if delimiter == '^' {
+                        VertAlignType::SuperScript
+                    } else {
+                        VertAlignType::SubScript
+                    };
+                    spans.push((&text[plain_start..open], None));
+                    spans.push((&text[content_start..close], Some(vert_align)));
+                    pos = close + delimiter.len_utf8();
+                    plain_start = pos;
+                }
+                _ => pos = content_start,
+            }
+        }
+        spans.push((&text[plain_start..], None));
+        spans
+    }
+
     fn add_text_run(
         &self,
         paragraph: DocxParagraph,
         text: &str,
-        bold: bool,
-        italic: bool,
-        mono: bool,
-        size: usize,
+        style: InlineStyle,
     ) -> DocxParagraph {
         if text.is_empty() {
             return paragraph;
         }
         let mut run = DocxRun::new().add_text(text.to_string());
 
-        if bold {
+        if style.bold {
             run = run.bold();
         }
-        if italic {
+        if style.italic {
             run = run.italic();
         }
+        if style.strike {
+            run = run.strike();
+        }
+        if style.underline {
+            run = run.underline("single");
+        }
+        if style.highlight {
+            run = run.highlight(&self.highlight_color);
+        }
+        if let Some(vert_align) = style.vert_align {
+            run.run_property = run.run_property.vert_align(vert_align);
+        }
 
-        if mono {
-            run = run.fonts(
-                RunFonts::new()
-                    .ascii(&self.mono_font_family)
-                    .hi_ansi(&self.mono_font_family),
-            );
+        if style.mono {
+            run = run.fonts(self.run_fonts(&self.mono_font_family));
         } else {
             // Apply body font
-            run = run.fonts(
-                RunFonts::new()
-                    .ascii(&self.default_font_family)
-                    .hi_ansi(&self.default_font_family),
-            );
+            run = run.fonts(self.run_fonts(&self.default_font_family));
         }
 
         // size > 0 means a specific caller (e.g., heading) provided size.
         // Otherwise use default body size.
-        let effective_size = if size > 0 {
-            size
+        let effective_size = if style.size > 0 {
+            style.size
         } else {
-            self.default_font_size
+            self.body_font_size()
         };
-        if effective_size > 0 {
-            run = run.size(effective_size);
-        }
+        run = run.size(effective_size);
 
         paragraph.add_run(run)
     }
 
+    // Latin font for the run plus the configured East Asian font, if any
+    fn run_fonts(&self, family: &str) -> RunFonts {
+        let fonts = RunFonts::new().ascii(family).hi_ansi(family);
+        match &self.east_asian_font_family {
+            Some(east_asian) => fonts.east_asia(east_asian),
+            None => fonts,
+        }
+    }
+
     fn collect_plain_text(&self, nodes: &[Node]) -> String {
         let mut buf = String::new();
         for n in nodes {
@@ -470,7 +2549,7 @@ impl DocxExporter {
         let base_before = 0u32;
         let base_after = 160u32;
 
-        let body_pt = self.default_font_size as f32 / 2.0;
+        let body_pt = self.body_font_size() as f32 / 2.0;
         let ratio = body_pt / 11.0;
         let scale = |v: u32| -> u32 {
             if v == 0 {
@@ -484,35 +2563,151 @@ impl DocxExporter {
         (scale(base_before), scale(base_after))
     }
 
+    // docx_rs seeds missing borders with single lines on every side; start from an empty
+    // set instead so only the requested edge is drawn.
+    fn add_paragraph_border(mut para: DocxParagraph, border: ParagraphBorder) -> DocxParagraph {
+        let borders = para
+            .property
+            .borders
+            .take()
+            .unwrap_or_else(ParagraphBorders::with_empty)
+            .set(border);
+        para.property = para.property.set_borders(borders);
+        para
+    }
+
+    // Auto line rule in 240ths of a line; `None` leaves Word's single spacing untouched
+    fn body_line_spacing(&self) -> Option<docx_rs::LineSpacing> {
+        let multiplier = self.line_spacing_multiplier.filter(|m| *m > 0.0)?;
+        Some(
+            docx_rs::LineSpacing::new()
+                .line_rule(LineSpacingType::Auto)
+                .line((multiplier * SINGLE_LINE_SPACING).round() as i32),
+        )
+    }
+
     fn new_body_paragraph(&self) -> DocxParagraph {
         let (before, after) = self.body_paragraph_spacing();
-        DocxParagraph::new().line_spacing(docx_rs::LineSpacing::new().before(before).after(after))
+        let spacing = self.body_line_spacing().unwrap_or_default();
+        self.apply_text_direction(
+            DocxParagraph::new().line_spacing(spacing.before(before).after(after)),
+        )
+    }
+
+    // RTL paragraphs get the bidi property. Word then treats indents and start alignment
+    // as logical: text, list markers and indentation start at the right margin without
+    // an explicit right alignment (which Word would mirror back to the left). Code blocks
+    // stay left-to-right.
+    fn apply_text_direction(&self, mut para: DocxParagraph) -> DocxParagraph {
+        if self.rtl {
+            para.property = para.property.bidi(true);
+        }
+        para
     }
 }
 
-impl Export for DocxExporter {
-    fn export(&self, content: &str) -> Result<Exported, MultiFormatExportError> {
-        let md_ast = markdown::to_mdast(content, &ParseOptions::default())?;
-        let mut docx = Docx::new();
+impl DocxExporter {
+    /// Render `content` and pack the `.docx` archive straight into `writer` instead of an
+    /// in-memory buffer. The zip container needs to seek back to patch entry headers, so
+    /// the writer must be `Seek` as well (e.g. a `File` or `Cursor`).
+    ///
+    /// Returns the same warnings as [`Exported::warnings`].
+    pub fn export_to_writer<W: Write + Seek>(
+        &self,
+        content: &str,
+        writer: &mut W,
+    ) -> Result<Vec<String>, MultiFormatExportError> {
+        let (docx, warnings) = self.build_docx(content)?;
+        let mut xml = docx.build();
+        if self.table_style.repeat_header {
+            xml.document = String::from_utf8_lossy(&xml.document)
+                .replace(TABLE_ROW_CANT_SPLIT, TABLE_HEADER_ROW)
+                .into_bytes();
+        }
+        if let Some(metadata) = &self.metadata {
+            xml.doc_props.core = metadata.core_properties_xml();
+        }
+        xml.pack(writer)
+            .map_err(|err| MultiFormatExportError::DocxError(err.to_string()))?;
+        Ok(warnings)
+    }
+
+    fn build_docx(&self, content: &str) -> Result<(Docx, Vec<String>), MultiFormatExportError> {
+        // GFM constructs are required for `Node::Table`, `Node::Delete` etc. to show up in
+        // the AST at all
+        let options = ParseOptions {
+            constructs: self.constructs.clone(),
+            // `~x~` is subscript; strikethrough needs `~~x~~`
+            gfm_strikethrough_single_tilde: false,
+            ..ParseOptions::default()
+        };
+        let md_ast = markdown::to_mdast(content, &options)?;
+        let margins = self.page_margins.unwrap_or_default();
+        let (page_width, page_height) = self.page_dimensions();
+        let mut docx = Docx::new().page_size(page_width, page_height).page_margin(
+            PageMargin::new()
+                .top(margins.top)
+                .bottom(margins.bottom)
+                .left(margins.left)
+                .right(margins.right),
+        );
+        if self.orientation == PageOrientation::Landscape {
+            docx = docx.page_orient(PageOrientationType::Landscape);
+        }
+        for style in self.heading_styles() {
+            docx = docx.add_style(style);
+        }
+        docx = docx.add_style(Self::footnote_reference_style());
+        if let Some(header) = self.render_header() {
+            docx = docx.header(header);
+        }
+        if let Some(footer) = self.render_footer() {
+            docx = docx.footer(footer);
+        }
+        if self.toc {
+            docx = docx.add_table_of_contents(self.render_toc());
+        }
+        let mut state = RenderState::default();
+        state.collect_definitions(&md_ast);
+        state.collect_heading_bookmarks(&md_ast);
+        state.heading_slugs.clear();
 
         if let Some(children) = md_ast.children() {
             for node in children {
-                for para in self.render_block_node(node, 0) {
-                    docx = docx.add_paragraph(para);
+                for block in self.render_block_node(node, 0, &mut state) {
+                    docx = match block {
+                        DocxBlock::Paragraph(para) => docx.add_paragraph(*para),
+                        DocxBlock::Table(table) => docx.add_table(*table),
+                    };
                 }
             }
         }
 
+        if !state.numberings.is_empty() {
+            docx = docx
+                .add_abstract_numbering(self.list_abstract_numbering(false))
+                .add_abstract_numbering(self.list_abstract_numbering(true));
+            for numbering in state.numberings {
+                docx = docx.add_numbering(numbering);
+            }
+        }
+
+        Ok((docx, state.warnings))
+    }
+}
+
+impl Export for DocxExporter {
+    fn export(&self, content: &str) -> Result<Exported, MultiFormatExportError> {
         let mut cursor = Cursor::new(Vec::new());
-        docx.build()
-            .pack(&mut cursor)
-            .map_err(|err| MultiFormatExportError::DocxError(err.to_string()))?;
+        let warnings = self.export_to_writer(content, &mut cursor)?;
         let bytes = Bytes::from(cursor.into_inner());
 
         Ok(Exported {
             data: bytes,
             mime: DOCX_MIME,
             extension: DOCX_EXTENSION,
+            warnings,
+            page_count: None, // pagination is up to the word processor
         })
     }
 }
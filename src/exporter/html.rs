@@ -1,25 +1,588 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::LazyLock,
+};
+
+use markdown::mdast::Node;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    html::{IncludeBackground, styled_line_to_highlighted_html},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
 use crate::{
     error::MultiFormatExportError,
-    exporter::{Export, Exported},
+    exporter::{Export, Exported, heading_slug},
 };
 
-pub struct HtmlExporter;
+pub struct HtmlExporter {
+    gfm: bool,           // false = strict CommonMark
+    sanitize: bool,      // false = raw HTML and any URL protocol pass through
+    full_document: bool, // false = body fragment only, for embedding
+    title: Option<String>,
+    highlight_code: bool,
+    highlight_theme: String, // one of syntect's default themes
+    heading_ids: bool,
+    heading_anchor_links: bool,
+    toc: bool,
+    toc_max_depth: u8,
+    css: Option<String>,
+    css_href: Option<String>,
+    css_in_fragment: bool,              // false = fragments come without styles
+    class_map: HashMap<String, String>, // tag name -> class attribute
+}
+
+// A heading as the table of contents lists it
+struct TocEntry {
+    level: u8,
+    text: String,
+    slug: String,
+}
+
+const HTML_EXTENSION: &str = "html";
+const HTML_MIME: &str = "text/html";
 
-const HTML_EXTENSION: &'static str = "html";
-const HTML_MIME: &'static str = "text/html";
+// Fenced code with a language, as markdown renders it
+const CODE_BLOCK_START: &str = "<pre><code class=\"language-";
+const CODE_BLOCK_END: &str = "</code></pre>";
+const DEFAULT_HIGHLIGHT_THEME: &str = "InspiredGitHub";
+// `[[TOC]]` on a line of its own, as markdown renders it
+const TOC_MARKER: &str = "<p>[[TOC]]</p>";
+// Private-use characters around a heading's index, put in front of its content in the
+// markdown so the `<hN>` rendered from it can be told apart from raw HTML headings
+const HEADING_MARK: char = '\u{E000}';
+const HEADING_MARK_END: char = '\u{E001}';
+
+// Loading the syntax definitions takes a while, so it happens on first use only
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+impl Default for HtmlExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl HtmlExporter {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            gfm: true,
+            sanitize: true,
+            full_document: false,
+            title: None,
+            highlight_code: false,
+            highlight_theme: DEFAULT_HIGHLIGHT_THEME.to_string(),
+            heading_ids: false,
+            heading_anchor_links: false,
+            toc: false,
+            toc_max_depth: 6,
+            css: None,
+            css_href: None,
+            css_in_fragment: false,
+            class_map: HashMap::new(),
+        }
+    }
+
+    /// Render GitHub Flavored Markdown: tables, strikethrough, task lists, autolinks and
+    /// footnotes (the default). `false` sticks to strict CommonMark.
+    pub fn with_gfm(mut self, gfm: bool) -> Self {
+        self.gfm = gfm;
+        self
+    }
+
+    /// Keep the output safe to show in a browser (the default): raw HTML in the markdown is
+    /// escaped and shown as text, so no `<script>`, event handler or other tag gets through,
+    /// and link/image URLs are limited to `http`, `https`, `mailto`, `irc`, `ircs` and
+    /// `xmpp` (images: `http`, `https`), which drops `javascript:` URLs. The only tags in
+    /// the output are the ones markdown itself produces.
+    ///
+    /// `false` passes raw HTML and every URL through unchanged; only for trusted content.
+    pub fn with_sanitize(mut self, sanitize: bool) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Wrap the output in a complete HTML5 document (doctype, UTF-8 charset, viewport)
+    /// instead of returning the bare body fragment.
+    pub fn with_full_document(mut self, full_document: bool) -> Self {
+        self.full_document = full_document;
+        self
+    }
+
+    /// `<title>` of the full document; ignored for fragments.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Syntax-highlight fenced code blocks with inline styles, picking the grammar from the
+    /// fence's language. Blocks in an unknown language stay plain
+    /// `<pre><code class="language-..">`.
+    pub fn with_highlight_code(mut self, highlight_code: bool) -> Self {
+        self.highlight_code = highlight_code;
+        self
+    }
+
+    /// Colour theme for highlighted code: `InspiredGitHub` (default), `Solarized (light)`,
+    /// `Solarized (dark)`, `base16-ocean.light`, `base16-ocean.dark`, `base16-eighties.dark`
+    /// or `base16-mocha.dark`. Unknown names fall back to the default with a warning.
+    pub fn with_highlight_theme(mut self, theme: impl Into<String>) -> Self {
+        self.highlight_theme = theme.into();
+        self
+    }
+
+    /// Give every markdown heading an `id` from its text, see [`heading_slug`], so `#slug`
+    /// links and URL fragments can point at it. Headings written as raw HTML stay as they
+    /// are.
+    pub fn with_heading_ids(mut self, heading_ids: bool) -> Self {
+        self.heading_ids = heading_ids;
+        self
+    }
+
+    /// With heading ids, end each heading with a `<a class="heading-anchor">#</a>` link to
+    /// itself, for copying deep links.
+    pub fn with_heading_anchor_links(mut self, anchor_links: bool) -> Self {
+        self.heading_anchor_links = anchor_links;
+        self
+    }
+
+    /// Replace each `[[TOC]]` paragraph with a nested `<ul>` of links to the headings.
+    /// Turns on heading ids, which the links point at.
+    pub fn with_toc(mut self, toc: bool) -> Self {
+        self.toc = toc;
+        self
+    }
+
+    /// Deepest heading level listed in the table of contents, 1 to 6 (default 6: all).
+    pub fn with_toc_max_depth(mut self, max_depth: u8) -> Self {
+        self.toc_max_depth = max_depth.clamp(1, 6);
+        self
+    }
+
+    /// Stylesheet put verbatim into a `<style>` block in the document's `<head>`.
+    pub fn with_css(mut self, css: impl Into<String>) -> Self {
+        self.css = Some(css.into());
+        self
+    }
+
+    /// URL of an external stylesheet, linked from the document's `<head>` (before the
+    /// [`with_css`](Self::with_css) block, which can then override it).
+    pub fn with_css_href(mut self, href: impl Into<String>) -> Self {
+        self.css_href = Some(href.into());
+        self
+    }
+
+    /// Put the stylesheet at the start of fragments too; by default fragments leave styling
+    /// to the page they are embedded in and only full documents carry it.
+    pub fn with_css_in_fragment(mut self, css_in_fragment: bool) -> Self {
+        self.css_in_fragment = css_in_fragment;
+        self
+    }
+
+    /// Classes to give elements by tag name, e.g. `"p"` -> `"prose-p"` or `"table"` ->
+    /// `"table table-striped"`. Elements that already carry a class (code blocks, the
+    /// table of contents, anchor links) get the mapped one added; unmapped elements stay
+    /// as they are.
+    pub fn with_class_map(mut self, class_map: HashMap<String, String>) -> Self {
+        self.class_map = class_map;
+        self
+    }
+
+    // Adds the mapped class to every opening tag in the class map
+    fn apply_classes(&self, html: &str) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            out.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            // closing tags and comments have no name here, so they never match
+            let name_len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let (Some(class), Some(tag_len)) =
+                (self.class_map.get(&rest[..name_len]), rest.find('>'))
+            else {
+                continue;
+            };
+            let tag = &rest[..tag_len];
+            let class = escape_html(class);
+            match class_value(tag) {
+                // merged into the existing attribute; a second one would be ignored
+                Some(value) => {
+                    let existing = &tag[value.clone()];
+                    let merged = match existing.chars().next() {
+                        Some('"' | '\'') => format!(
+                            "{} {class}{}",
+                            &existing[..existing.len() - 1],
+                            &existing[existing.len() - 1..]
+                        ),
+                        _ => format!("\"{existing} {class}\""),
+                    };
+                    out.push_str(&format!(
+                        "{}{merged}{}",
+                        &tag[..value.start],
+                        &tag[value.end..]
+                    ));
+                }
+                None => out.push_str(&format!(
+                    "{} class=\"{class}\"{}",
+                    &tag[..name_len],
+                    &tag[name_len..]
+                )),
+            }
+            rest = &rest[tag_len..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    // `<link>` and `<style>` for the configured stylesheets, one per line
+    fn style_tags(&self) -> String {
+        let mut tags = String::new();
+        if let Some(href) = &self.css_href {
+            tags.push_str(&format!(
+                "<link rel=\"stylesheet\" href=\"{}\">\n",
+                escape_html(href)
+            ));
+        }
+        if let Some(css) = &self.css {
+            tags.push_str(&format!("<style>\n{}\n</style>\n", css.trim_end()));
+        }
+        tags
+    }
+
+    // The mark in front of each heading's content becomes the `id` of the `<hN>` it ends up
+    // in (plus the anchor link)
+    fn anchor_headings(&self, html: &str, headings: &[TocEntry]) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find(HEADING_MARK) {
+            let before = &rest[..start];
+            let after = &rest[start + HEADING_MARK.len_utf8()..];
+            let marked = after
+                .split_once(HEADING_MARK_END)
+                .and_then(|(index, tail)| {
+                    Some((
+                        headings.get(index.parse::<usize>().ok()?)?,
+                        tail.strip_prefix(' ')?,
+                    ))
+                });
+            let Some((heading, tail)) = marked else {
+                out.push_str(&rest[..start + HEADING_MARK.len_utf8()]);
+                rest = after;
+                continue;
+            };
+            let (level, slug) = (heading.level, &heading.slug);
+            match before.strip_suffix(&format!("<h{level}>")) {
+                Some(before) => out.push_str(&format!("{before}<h{level} id=\"{slug}\">")),
+                None => out.push_str(before),
+            }
+            rest = tail;
+            if self.heading_anchor_links
+                && let Some(end) = rest.find(&format!("</h{level}>"))
+            {
+                out.push_str(&rest[..end]);
+                out.push_str(&format!(
+                    "<a class=\"heading-anchor\" href=\"#{slug}\">#</a>"
+                ));
+                rest = &rest[end..];
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    // Nested lists, one level per heading level; skipped levels get an empty item
+    fn render_toc(&self, headings: &[TocEntry]) -> String {
+        let listed: Vec<_> = headings
+            .iter()
+            .filter(|heading| heading.level <= self.toc_max_depth)
+            .collect();
+        let Some(top) = listed.iter().map(|heading| heading.level).min() else {
+            return String::new();
+        };
+
+        let mut out = String::from("<nav class=\"toc\">\n");
+        // per open `<ul>`: whether its last `<li>` is still open
+        let mut open: Vec<bool> = Vec::new();
+        for heading in listed {
+            let depth = usize::from(heading.level - top) + 1;
+            while open.len() > depth {
+                if open.pop() == Some(true) {
+                    out.push_str("</li>\n");
+                }
+                out.push_str("</ul>\n");
+            }
+            if open.len() == depth && open[depth - 1] {
+                out.push_str("</li>\n");
+            }
+            while open.len() < depth {
+                if let Some(item_open) = open.last_mut()
+                    && !*item_open
+                {
+                    out.push_str("<li>\n");
+                    *item_open = true;
+                }
+                out.push_str("<ul>\n");
+                open.push(false);
+            }
+            out.push_str(&format!(
+                "<li><a href=\"#{}\">{}</a>",
+                heading.slug,
+                escape_html(&heading.text)
+            ));
+            open[depth - 1] = true;
+        }
+        while let Some(item_open) = open.pop() {
+            if item_open {
+                out.push_str("</li>\n");
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("</nav>");
+        out
+    }
+
+    fn highlight_code_blocks(&self, html: &str, warnings: &mut Vec<String>) -> String {
+        let theme = match THEMES.themes.get(&self.highlight_theme) {
+            Some(theme) => theme,
+            None => {
+                warnings.push(format!(
+                    "unknown highlight theme {:?}, using {DEFAULT_HIGHLIGHT_THEME}",
+                    self.highlight_theme
+                ));
+                &THEMES.themes[DEFAULT_HIGHLIGHT_THEME]
+            }
+        };
+
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find(CODE_BLOCK_START) {
+            out.push_str(&rest[..start]);
+            let block = &rest[start..];
+            let inner = &block[CODE_BLOCK_START.len()..];
+            let (Some(lang_end), Some(code_end)) = (inner.find("\">"), inner.find(CODE_BLOCK_END))
+            else {
+                rest = block;
+                break;
+            };
+            let lang = &inner[..lang_end];
+            let code = unescape_html(&inner[lang_end + 2..code_end]);
+            let block_len = CODE_BLOCK_START.len() + code_end + CODE_BLOCK_END.len();
+            match highlight(&code, lang, theme) {
+                Some(spans) => {
+                    let background = theme
+                        .settings
+                        .background
+                        .map(|c| {
+                            format!(
+                                " style=\"background-color:#{:02x}{:02x}{:02x};\"",
+                                c.r, c.g, c.b
+                            )
+                        })
+                        .unwrap_or_default();
+                    out.push_str(&format!(
+                        "<pre{background}><code class=\"language-{lang}\">{spans}</code></pre>"
+                    ));
+                }
+                None => out.push_str(&block[..block_len]),
+            }
+            rest = &block[block_len..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn wrap_document(&self, body: &str) -> String {
+        let title = self
+            .title
+            .as_deref()
+            .map(|title| format!("<title>{}</title>\n", escape_html(title)))
+            .unwrap_or_default();
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             {title}{}</head>\n<body>\n{}\n</body>\n</html>\n",
+            self.style_tags(),
+            body.trim_end()
+        )
+    }
+}
+
+// Inline-styled spans for `code`, or `None` when there is no grammar for `lang`
+fn highlight(code: &str, lang: &str, theme: &Theme) -> Option<String> {
+    let syntax = SYNTAXES.find_syntax_by_token(&unescape_html(lang))?;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut spans = String::new();
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(line, &SYNTAXES).ok()?;
+        spans.push_str(&styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok()?);
+    }
+    Some(spans)
+}
+
+// Repeated headings get `-1`, `-2`, ... appended, like the other exporters' anchors
+fn unique_slug(slugs: &mut HashSet<String>, text: &str) -> String {
+    let base = heading_slug(text);
+    let mut slug = base.clone();
+    let mut suffix = 0;
+    while !slugs.insert(slug.clone()) {
+        suffix += 1;
+        slug = format!("{base}-{suffix}");
+    }
+    slug
+}
+
+// Headings in document order with their anchors, and the markdown with each heading's
+// content tagged by its index in that list, see `HEADING_MARK`
+fn mark_headings(
+    content: &str,
+    options: &markdown::ParseOptions,
+) -> Result<(String, Vec<TocEntry>), MultiFormatExportError> {
+    let root = markdown::to_mdast(content, options)?;
+    let mut headings = Vec::new();
+    let mut offsets = Vec::new();
+    collect_headings(&root, &mut HashSet::new(), &mut headings, &mut offsets);
+
+    let mut marked = String::with_capacity(content.len() + 8 * offsets.len());
+    let mut copied = 0;
+    for (index, offset) in offsets.into_iter().enumerate() {
+        marked.push_str(&content[copied..offset]);
+        marked.push_str(&format!("{HEADING_MARK}{index}{HEADING_MARK_END} "));
+        copied = offset;
+    }
+    marked.push_str(&content[copied..]);
+    Ok((marked, headings))
+}
+
+// Headings without any text get no anchor, as there is nothing to build it from
+fn collect_headings(
+    node: &Node,
+    slugs: &mut HashSet<String>,
+    headings: &mut Vec<TocEntry>,
+    offsets: &mut Vec<usize>,
+) {
+    if let Node::Heading(heading) = node {
+        let text = node.to_string();
+        if !heading_slug(&text).is_empty()
+            && let Some(position) = heading.children.first().and_then(Node::position)
+        {
+            offsets.push(position.start.offset);
+            headings.push(TocEntry {
+                level: heading.depth,
+                slug: unique_slug(slugs, &text),
+                text,
+            });
+        }
+    }
+    if let Some(children) = node.children() {
+        for child in children {
+            collect_headings(child, slugs, headings, offsets);
+        }
+    }
+}
+
+// Byte range of the `class` attribute's value in an opening tag, quotes included; the
+// value may be double-, single- or unquoted, as raw HTML can use any of them
+fn class_value(tag: &str) -> Option<Range<usize>> {
+    // ASCII lowercasing keeps byte offsets
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find("class") {
+        let name_start = from + pos;
+        from = name_start + "class".len();
+        if !lower[..name_start].ends_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+        let Some(value) = lower[from..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let start = tag.len() - value.len();
+        let len = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].find(quote)? + 2,
+            _ => value
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(value.len()),
+        };
+        return Some(start..start + len);
+    }
+    None
+}
+
+// Reverses the escaping markdown applies to code and attribute values
+fn unescape_html(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
     }
+    out
 }
 
 impl Export for HtmlExporter {
     fn export(&self, content: &str) -> Result<Exported, MultiFormatExportError> {
+        let mut options = if self.gfm {
+            markdown::Options::gfm()
+        } else {
+            markdown::Options::default()
+        };
+        options.compile.allow_dangerous_html = !self.sanitize;
+        options.compile.allow_dangerous_protocol = !self.sanitize;
+        let mut warnings = Vec::new();
+        let (content, headings) = if self.heading_ids || self.toc {
+            let (marked, headings) = mark_headings(content, &options.parse)?;
+            (Cow::Owned(marked), headings)
+        } else {
+            (Cow::Borrowed(content), Vec::new())
+        };
+        let mut body = markdown::to_html_with_options(&content, &options)?;
+        if self.highlight_code {
+            body = self.highlight_code_blocks(&body, &mut warnings);
+        }
+        if self.heading_ids || self.toc {
+            body = self.anchor_headings(&body, &headings);
+            if self.toc {
+                if body.contains(TOC_MARKER) {
+                    body = body.replace(TOC_MARKER, &self.render_toc(&headings));
+                } else {
+                    warnings.push("no [[TOC]] marker, table of contents left out".to_string());
+                }
+            }
+        }
+        if !self.class_map.is_empty() {
+            body = self.apply_classes(&body);
+        }
+        let html = if self.full_document {
+            self.wrap_document(&body)
+        } else if self.css_in_fragment {
+            self.style_tags() + &body
+        } else {
+            body
+        };
         Ok(Exported {
-            data: markdown::to_html(content).into(),
+            data: html.into(),
             mime: HTML_MIME,
             extension: HTML_EXTENSION,
+            warnings,
+            page_count: None,
         })
     }
 }
@@ -3,11 +3,17 @@ use crate::{
     exporter::{Export, Exported},
 };
 
-const MARKDOWN_MIME: &'static str = "text/markdown";
-const MARKDOWN_EXTENSION: &'static str = "md";
+const MARKDOWN_MIME: &str = "text/markdown";
+const MARKDOWN_EXTENSION: &str = "md";
 
 pub struct MarkdownExporter;
 
+impl Default for MarkdownExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MarkdownExporter {
     pub fn new() -> Self {
         Self {}
@@ -20,6 +26,8 @@ impl Export for MarkdownExporter {
             data: content.to_string().into(),
             mime: MARKDOWN_MIME,
             extension: MARKDOWN_EXTENSION,
+            warnings: Vec::new(),
+            page_count: None,
         })
     }
 }
@@ -7,11 +7,63 @@ pub mod html;
 pub mod markdown;
 pub mod pdf;
 
+/// Maps an image URL/path from the markdown to the raw image bytes.
+/// Returning `None` marks the image as unresolvable.
+pub type ImageResolver = Box<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Anchor the exporters give a heading with this text, GitHub style: lowercase, spaces to
+/// hyphens, punctuation other than `-`/`_` dropped (`"Hello, World!"` -> `hello-world`).
+/// Repeated headings get `-1`, `-2`, ... appended to it in document order.
+pub fn heading_slug(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            _ if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Output of an export. More fields may be added, so outside this crate it is built with
+/// [`Exported::new`].
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct Exported {
     pub data: Bytes,
     pub mime: &'static str,
     pub extension: &'static str,
+    /// Content the exporter could not represent and left out (e.g. unsupported Markdown
+    /// nodes), and non-fatal problems reported while rendering (e.g. Typst warnings about
+    /// unknown fonts). Empty when everything was rendered.
+    pub warnings: Vec<String>,
+    /// Number of pages, for paginated formats (PDF); `None` for the others.
+    pub page_count: Option<usize>,
+}
+
+impl Exported {
+    /// Result without warnings or page count, for exporters outside this crate.
+    pub fn new(data: impl Into<Bytes>, mime: &'static str, extension: &'static str) -> Self {
+        Self {
+            data: data.into(),
+            mime,
+            extension,
+            warnings: Vec::new(),
+            page_count: None,
+        }
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    pub fn with_page_count(mut self, page_count: usize) -> Self {
+        self.page_count = Some(page_count);
+        self
+    }
 }
 
 pub trait Export: Send + Sync {
@@ -50,6 +50,12 @@ impl FromStr for OutputFormat {
     }
 }
 
+impl Default for MultiFormatExportEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MultiFormatExportEngine {
     pub fn new() -> Self {
         let handlebars = Handlebars::new();
@@ -82,7 +88,7 @@ impl MultiFormatExportEngine {
     ) -> Result<String, MultiFormatExportError> {
         self.handlebars
             .render(name, data)
-            .map_err(|e| MultiFormatExportError::RenderError(e))
+            .map_err(MultiFormatExportError::RenderError)
     }
 
     pub fn supported_formats(&self) -> Vec<OutputFormat> {
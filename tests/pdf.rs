@@ -0,0 +1,96 @@
+use multi_format_export_rs::exporter::{
+    Export,
+    pdf::{PdfExporter, PdfStandard},
+};
+
+const SAMPLE: &str = "# Title\n\nSome *emphasis*, **strong** text and `code`.\n\n- one\n- two\n";
+
+#[test]
+fn default_font_has_no_missing_font_warnings() {
+    let exported = PdfExporter::default().export(SAMPLE).unwrap();
+    assert!(exported.warnings.is_empty(), "{:?}", exported.warnings);
+}
+
+#[test]
+fn pdf_a_output_declares_conformance() {
+    let exported = PdfExporter::default()
+        .with_pdf_standard(PdfStandard::PdfA2b)
+        .export(SAMPLE)
+        .unwrap();
+    let pdf = String::from_utf8_lossy(&exported.data);
+    assert!(pdf.starts_with("%PDF-"));
+    // the XMP metadata stream is stored uncompressed, as PDF/A requires
+    assert!(pdf.contains("<pdfaid:part>2</pdfaid:part>"));
+    assert!(pdf.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+}
+
+#[test]
+fn headings_with_non_ascii_symbols_can_be_linked() {
+    let md = "See [area](#area-in-m²) and [sugar](#add-½-cup).\n\n# Area in m²\n\n# Add ½ cup\n";
+    let exported = PdfExporter::default().export(md).unwrap();
+    assert!(exported.warnings.is_empty(), "{:?}", exported.warnings);
+}
+
+#[test]
+fn tex_arguments_with_commas_and_semicolons_compile() {
+    let exporter = PdfExporter::default().with_math(true);
+    for math in [
+        r"$\frac{a,b}{c}$",
+        r"$\frac{a;b}{c}$",
+        r"$\sqrt{x, y}$",
+        r"$\sqrt[n,m]{x}$",
+        r"$\mathbf{x,y}$",
+        r"$\frac{\frac{a}{b}}{c, d}$",
+    ] {
+        let exported = exporter
+            .export(math)
+            .unwrap_or_else(|e| panic!("{math}: {e}"));
+        assert!(
+            exported.warnings.is_empty(),
+            "{math}: {:?}",
+            exported.warnings
+        );
+    }
+}
+
+#[test]
+fn undecodable_images_fall_back_to_alt_text() {
+    let mut png = Vec::new();
+    image::RgbImage::from_pixel(4, 4, image::Rgb([200, 10, 10]))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let truncated = png[..png.len() / 2].to_vec();
+    let exporter = PdfExporter::default().with_image_resolver(move |url| match url {
+        "ok.png" => Some(png.clone()),
+        "truncated.png" => Some(truncated.clone()),
+        _ => Some(b"<html><body>404 Not Found</body></html>".to_vec()),
+    });
+
+    let md = "![fine](ok.png)\n\n![cut off](truncated.png)\n\nInline ![page](404.png) image.\n";
+    let exported = exporter.export(md).unwrap();
+    assert_eq!(exported.warnings.len(), 2, "{:?}", exported.warnings);
+    assert!(exported.warnings[0].starts_with("image truncated.png left out"));
+    assert!(exported.warnings[1].starts_with("image 404.png left out"));
+}
+
+#[test]
+fn template_can_repeat_the_content() {
+    let template = "{{content}}\n#pagebreak()\n{{content}}".to_string();
+    let md = "# Summary\n\nSee [the summary](#summary).[^1]\n\n[^1]: A footnote.\n";
+    let exported = PdfExporter::new(template, None, &[]).export(md).unwrap();
+    assert_eq!(exported.page_count, Some(2));
+    assert!(exported.warnings.is_empty(), "{:?}", exported.warnings);
+}
+
+#[test]
+fn literal_content_marker_in_the_body_stays_text() {
+    let exporter = PdfExporter::new("{{content}}".to_string(), None, &[]);
+    let paragraphs: String = (1..=25).map(|n| format!("Paragraph {n}.\n\n")).collect();
+    let pages = |md: &str| exporter.export(md).unwrap().page_count;
+
+    let plain = format!("Literal marker.\n\n{paragraphs}");
+    let literal = format!("Literal {{{{content}}}} and `{{{{content}}}}`.\n\n{paragraphs}");
+    assert_eq!(pages(&literal), pages(&plain));
+    // the body is long enough that a second copy would add a page
+    assert!(pages(&format!("{plain}{plain}")) > pages(&plain));
+}